@@ -0,0 +1,145 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::piece::{Color, PieceType};
+
+// Names of the set and themes built into the frontend bundle
+const BUNDLED_PIECE_SET: &str = "classic";
+const BUNDLED_BOARD_THEMES: [(&str, &str, &str); 2] = [("classic", "#f0d9b5", "#b58863"), ("green", "#eeeed2", "#769656")];
+const IMAGE_EXTENSIONS: [&str; 2] = ["png", "svg"];
+
+/// A set of piece images, either bundled or installed as a folder under `piece-sets` in the app data
+/// directory.
+#[derive(Serialize, Clone, Debug)]
+pub struct PieceSet {
+    pub name: String,
+    // Folder holding the images, None for the bundled set
+    pub path: Option<PathBuf>,
+    // Why an installed set cannot be used, e.g. a missing image
+    pub problem: Option<String>,
+}
+
+/// Light and dark square colors, bundled or installed as `board-themes/<name>.json`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BoardTheme {
+    #[serde(default)]
+    pub name: String,
+    pub light: String,
+    pub dark: String,
+}
+
+/// The piece set and board theme the user picked, kept in `settings.json`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AppearanceSettings {
+    pub piece_set: String,
+    pub board_theme: String,
+}
+
+impl Default for AppearanceSettings {
+    fn default() -> Self {
+        AppearanceSettings { piece_set: BUNDLED_PIECE_SET.to_string(), board_theme: BUNDLED_BOARD_THEMES[0].0.to_string() }
+    }
+}
+
+/// The bundled piece set followed by those installed in `<data_dir>/piece-sets`. A folder is a set
+/// when it holds an image for every piece, named like the bundled ones, e.g. `White_King.png`.
+pub fn list_piece_sets(data_dir: &Path) -> Vec<PieceSet> {
+    let mut sets = vec![PieceSet { name: BUNDLED_PIECE_SET.to_string(), path: None, problem: None }];
+    let Ok(entries) = fs::read_dir(data_dir.join("piece-sets")) else {
+        return sets;
+    };
+    let mut installed: Vec<PieceSet> = entries.flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .map(|path| PieceSet {
+            name: path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default(),
+            problem: missing_image(&path).map(|image| format!("{} has no image for {}", path.display(), image)),
+            path: Some(path),
+        })
+        .collect();
+    installed.sort_by(|a, b| a.name.cmp(&b.name));
+    sets.extend(installed);
+    sets
+}
+
+/// The bundled themes followed by those installed in `<data_dir>/board-themes`. Files that are not
+/// a valid theme are skipped.
+pub fn list_board_themes(data_dir: &Path) -> Vec<BoardTheme> {
+    let mut themes: Vec<BoardTheme> = BUNDLED_BOARD_THEMES.iter()
+        .map(|&(name, light, dark)| BoardTheme { name: name.to_string(), light: light.to_string(), dark: dark.to_string() })
+        .collect();
+    let Ok(entries) = fs::read_dir(data_dir.join("board-themes")) else {
+        return themes;
+    };
+    let mut installed: Vec<BoardTheme> = entries.flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "json"))
+        .filter_map(|path| {
+            let json = fs::read_to_string(&path).ok()?;
+            let theme: BoardTheme = serde_json::from_str(&json).ok()?;
+            let name = path.file_stem()?.to_string_lossy().into_owned();
+            let valid = is_css_color(&theme.light) && is_css_color(&theme.dark);
+            valid.then_some(BoardTheme { name, ..theme })
+        })
+        .filter(|theme| !BUNDLED_BOARD_THEMES.iter().any(|&(name, _, _)| name == theme.name))
+        .collect();
+    installed.sort_by(|a, b| a.name.cmp(&b.name));
+    themes.extend(installed);
+    themes
+}
+
+/// The saved appearance, or the defaults if nothing has been picked yet.
+pub fn load_settings(data_dir: &Path) -> AppearanceSettings {
+    fs::read_to_string(settings_path(data_dir)).ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+pub fn select_piece_set(data_dir: &Path, name: &str) -> Result<AppearanceSettings, String> {
+    let set = list_piece_sets(data_dir).into_iter()
+        .find(|set| set.name == name)
+        .ok_or_else(|| format!("There is no piece set named \"{}\"", name))?;
+    if let Some(problem) = set.problem {
+        return Err(problem);
+    }
+    let settings = AppearanceSettings { piece_set: set.name, ..load_settings(data_dir) };
+    save_settings(data_dir, &settings)?;
+    Ok(settings)
+}
+
+pub fn select_board_theme(data_dir: &Path, name: &str) -> Result<AppearanceSettings, String> {
+    let theme = list_board_themes(data_dir).into_iter()
+        .find(|theme| theme.name == name)
+        .ok_or_else(|| format!("There is no board theme named \"{}\"", name))?;
+    let settings = AppearanceSettings { board_theme: theme.name, ..load_settings(data_dir) };
+    save_settings(data_dir, &settings)?;
+    Ok(settings)
+}
+
+fn save_settings(data_dir: &Path, settings: &AppearanceSettings) -> Result<(), String> {
+    let path = settings_path(data_dir);
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|error| format!("Failed to create {}: {}", dir.display(), error))?;
+    }
+    let json = serde_json::to_string_pretty(settings).map_err(|error| error.to_string())?;
+    fs::write(&path, json).map_err(|error| format!("Failed to write {}: {}", path.display(), error))
+}
+
+fn settings_path(data_dir: &Path) -> PathBuf {
+    data_dir.join("settings.json")
+}
+
+// The first piece image, such as "White_King", that the folder lacks in every supported format
+fn missing_image(dir: &Path) -> Option<String> {
+    [Color::White, Color::Black].into_iter()
+        .flat_map(|color| PieceType::ALL.into_iter().map(move |piece_type| format!("{:?}_{:?}", color, piece_type)))
+        .find(|image| !IMAGE_EXTENSIONS.iter().any(|extension| dir.join(format!("{}.{}", image, extension)).is_file()))
+}
+
+// Accepts the #rgb and #rrggbb forms
+fn is_css_color(color: &str) -> bool {
+    color.strip_prefix('#')
+        .is_some_and(|hex| matches!(hex.len(), 3 | 6) && hex.chars().all(|c| c.is_ascii_hexdigit()))
+}
@@ -1,10 +1,207 @@
-use crate::game::{piece::{Piece, Position, Square}, state::GameState};
-use crate::game::board::BOARD_SIZE;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
 
+use serde::Serialize;
+use tauri::{AppHandle, ClipboardManager, Invoke, Manager, State};
 
+use crate::appearance::{self, AppearanceSettings, BoardTheme, PieceSet};
+use crate::game::{board::ChessBoard, error::MoveError, moves::Move, notation::{self, NotationStyle}, piece::{Color, Piece, PieceType, Position}, state::{GameMetadata, GameState, MaterialBalance, PlayerNames, UndoPolicy}};
+use crate::game::scoresheet::{self, ScoresheetImport};
+use crate::game::diagram;
+use crate::game::database::{GameDatabase, GameQuery, MonthlyDigest, StoredGame};
+use crate::game::kriegspiel::{self, KriegspielView};
+use crate::game::saves::{self, SavedGameInfo};
+use crate::game::positions::{self, NamedPosition};
+use crate::game::setup::parse_setup;
+use crate::game::eco::Opening;
+use crate::game::epd::EpdRecord;
+use crate::game::cursor::{BoardCursor, CursorSelection, CursorView, Direction};
+use crate::game::journal::{self, Journal, ReplayReport};
+use crate::game::script::{self, ScriptReport};
+use crate::game::pgn::{self, ImportFinished, ImportProgress, PgnLibrary, PgnPreview};
+use crate::game::analysis::{AnalysisBoard, AnalysisView};
+
+
+fn lock_game<'a>(state: &'a State<'_, Mutex<GameState>>) -> Result<MutexGuard<'a, GameState>, String> {
+    state.lock().map_err(|_| "Failed to lock game state".to_string())
+}
+
+fn lock_analysis<'a>(state: &'a State<'_, Mutex<AnalysisBoard>>) -> Result<MutexGuard<'a, AnalysisBoard>, String> {
+    state.lock().map_err(|_| "Failed to lock analysis board".to_string())
+}
+
+// Events are best effort: a missing listener should never fail the command itself
+fn emit_event<S: Serialize + Clone>(app: &AppHandle, event: &str, payload: S) {
+    if let Err(error) = app.emit_all(event, payload) {
+        eprintln!("Failed to emit {} event: {}", event, error);
+    }
+}
+
+// Lets every listener (board, AI, multiplayer) react to the end of the game, and files the game in
+// the database
+fn handle_game_over(app: &AppHandle, game: &GameState) {
+    if game.is_game_over() {
+        emit_event(app, "game-over", game.clone());
+        record_game(app, game);
+    }
+}
+
+// Like events, recording is best effort: a database problem should not undo the end of the game
+fn record_game(app: &AppHandle, game: &GameState) {
+    // A set-up position that was already over has no game worth keeping.
+    if game.move_history().is_empty() {
+        return;
+    }
+    let date = match game.metadata().date.as_str() {
+        "" => chrono::Local::now().format("%Y.%m.%d").to_string(),
+        date => date.to_string(),
+    };
+    let opening = game.opening().map(ToString::to_string);
+    let database = app.state::<Mutex<GameDatabase>>();
+    let stored = database.lock()
+        .map_err(|_| "Failed to lock game database".to_string())
+        .and_then(|database| database.store(game, &date, opening.as_deref()));
+    if let Err(error) = stored {
+        eprintln!("Failed to record the game: {}", error);
+    }
+}
+
+// Kriegspiel players only learn what the referee tells them, so the commands that would show them
+// the whole board refuse while it is on
+fn ensure_board_shown(game: &GameState) -> Result<(), MoveError> {
+    if game.is_kriegspiel() {
+        return Err(MoveError::PlayingKriegspiel);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_game_state(state: State<'_, Mutex<GameState>>) -> Result<GameState, String> {
+    let game = lock_game(&state)?;
+    ensure_board_shown(&game).map_err(|error| error.to_string())?;
+    Ok(game.clone())
+}
+
+#[tauri::command]
+pub fn move_piece(from: Position, to: Position, promotion: Option<PieceType>, app: AppHandle, state: State<'_, Mutex<GameState>>) -> Result<GameState, MoveError> {
+    let mut game = state.lock().map_err(|_| MoveError::StateUnavailable)?;
+    ensure_board_shown(&game)?;
+    play_move(&app, &mut game, from, to, promotion)?;
+    Ok(game.clone())
+}
+
+fn play_move(app: &AppHandle, game: &mut GameState, from: Position, to: Position, promotion: Option<PieceType>) -> Result<(), MoveError> {
+    game.move_piece_from(from, to, promotion)?;
+    announce_move(app, game);
+    Ok(())
+}
+
+// Tells every listener about the move just played
+fn announce_move(app: &AppHandle, game: &GameState) {
+    if game.is_game_over() {
+        handle_game_over(app, game);
+    } else {
+        emit_event(app, "turn-changed", game.turn_changed());
+    }
+}
+
+fn lock_cursor<'a>(state: &'a State<'_, Mutex<BoardCursor>>) -> Result<MutexGuard<'a, BoardCursor>, MoveError> {
+    state.lock().map_err(|_| MoveError::StateUnavailable)
+}
+
+/// Moves the selection cursor one square, for playing with a gamepad or remote.
 #[tauri::command]
-pub fn get_game_state() -> Result<GameState, String> {
-    Ok(GameState::new()) // Assumes `GameState::new()` initializes the game state
+pub fn cursor_move(direction: Direction, cursor: State<'_, Mutex<BoardCursor>>, state: State<'_, Mutex<GameState>>) -> Result<CursorView, MoveError> {
+    let game = state.lock().map_err(|_| MoveError::StateUnavailable)?;
+    ensure_board_shown(&game)?;
+    let mut cursor = lock_cursor(&cursor)?;
+    cursor.step(direction, game.turn_changed().orientation);
+    Ok(cursor.view(&game))
+}
+
+/// Picks up or puts down a piece on the square under the cursor. Putting a piece down on one of
+/// its targets plays the move, which is announced like any other. A pawn reaching the last rank
+/// becomes `promotion`, a queen if none is given.
+#[tauri::command]
+pub fn cursor_select(promotion: Option<PieceType>, app: AppHandle, cursor: State<'_, Mutex<BoardCursor>>, state: State<'_, Mutex<GameState>>) -> Result<CursorSelection, MoveError> {
+    let mut game = state.lock().map_err(|_| MoveError::StateUnavailable)?;
+    ensure_board_shown(&game)?;
+    let mut cursor = lock_cursor(&cursor)?;
+    if let Some((from, to)) = cursor.select(&game)? {
+        play_move(&app, &mut game, from, to, promotion)?;
+    }
+    Ok(CursorSelection { cursor: cursor.view(&game), game: game.clone() })
+}
+
+#[tauri::command]
+pub fn undo_move(app: AppHandle, state: State<'_, Mutex<GameState>>) -> Result<GameState, String> {
+    let mut game = lock_game(&state)?;
+    game.undo_move()?;
+    emit_event(&app, "turn-changed", game.turn_changed());
+    Ok(game.clone())
+}
+
+/// Sets how many moves may be taken back in the current game: without limit, a fixed number, or none.
+#[tauri::command]
+pub fn set_undo_policy(policy: UndoPolicy, state: State<'_, Mutex<GameState>>) -> Result<GameState, String> {
+    let mut game = lock_game(&state)?;
+    game.set_undo_policy(policy);
+    Ok(game.clone())
+}
+
+/// Takes the live game back to `index` plies, so play continues from there.
+#[tauri::command]
+pub fn goto_move(index: usize, app: AppHandle, state: State<'_, Mutex<GameState>>) -> Result<GameState, String> {
+    let mut game = lock_game(&state)?;
+    game.goto_move(index)?;
+    emit_event(&app, "turn-changed", game.turn_changed());
+    Ok(game.clone())
+}
+
+/// The game as it stood after `index` plies, leaving the live game untouched.
+#[tauri::command]
+pub fn get_position_at(index: usize, state: State<'_, Mutex<GameState>>) -> Result<GameState, String> {
+    lock_game(&state)?.position_at(index)
+}
+
+/// Starts browsing the game's history at `index` plies, returning that position. The live game is
+/// left untouched and refuses moves until `leave_review`, so clicking on the board while browsing
+/// cannot play one.
+#[tauri::command]
+pub fn enter_review(index: usize, state: State<'_, Mutex<GameState>>) -> Result<GameState, String> {
+    lock_game(&state)?.review(index)
+}
+
+/// Stops browsing the game's history and goes back to the current position.
+#[tauri::command]
+pub fn leave_review(state: State<'_, Mutex<GameState>>) -> Result<GameState, String> {
+    let mut game = lock_game(&state)?;
+    game.leave_review();
+    Ok(game.clone())
+}
+
+#[tauri::command]
+pub fn set_player_names(players: PlayerNames, state: State<'_, Mutex<GameState>>) -> Result<GameState, String> {
+    let mut game = lock_game(&state)?;
+    game.set_player_names(players);
+    Ok(game.clone())
+}
+
+/// Sets the players and the PGN headers of the active game, used when it is exported or saved.
+#[tauri::command]
+pub fn set_game_metadata(players: PlayerNames, metadata: GameMetadata, state: State<'_, Mutex<GameState>>) -> Result<GameState, String> {
+    let mut game = lock_game(&state)?;
+    game.set_player_names(players);
+    game.set_metadata(metadata);
+    Ok(game.clone())
+}
+
+#[tauri::command]
+pub fn resign(color: Color, app: AppHandle, state: State<'_, Mutex<GameState>>) -> Result<GameState, String> {
+    let mut game = lock_game(&state)?;
+    game.resign(color)?;
+    handle_game_over(&app, &game);
+    Ok(game.clone())
 }
 
 #[tauri::command]
@@ -20,14 +217,529 @@ pub fn greet(name: &str) -> String {
 }
 
 #[tauri::command]
-pub fn get_possible_moves(x: usize, y: usize, board: Vec<Vec<Option<Square>>>) -> Vec<Position> {
-    // Ensure the board is accessed correctly.
-    if let Some(row) = board.get(y) {
-        if let Some(Some(square)) = row.get(x) {
-            println!("Square: {:?}", square);
-            return square.calculate_moves(Position::new(x, y), &board);
+pub fn get_fen(state: State<'_, Mutex<GameState>>) -> Result<String, String> {
+    Ok(lock_game(&state)?.to_fen())
+}
+
+/// The ECO code and name of the opening the game has reached, if any.
+#[tauri::command]
+pub fn get_opening_info(state: State<'_, Mutex<GameState>>) -> Result<Option<Opening>, String> {
+    Ok(lock_game(&state)?.opening().cloned())
+}
+
+#[tauri::command]
+pub fn get_material_balance(state: State<'_, Mutex<GameState>>) -> Result<MaterialBalance, String> {
+    Ok(lock_game(&state)?.material())
+}
+
+/// Every legal move for the side to move, with castling, en passant and promotions spelled out.
+#[tauri::command]
+pub fn get_legal_moves(state: State<'_, Mutex<GameState>>) -> Result<Vec<Move>, String> {
+    let game = lock_game(&state)?;
+    ensure_board_shown(&game).map_err(|error| error.to_string())?;
+    game.ensure_in_progress().map_err(|error| error.to_string())?;
+    Ok(game.board().all_legal_moves(game.current_player()))
+}
+
+#[tauri::command]
+pub fn get_possible_moves(x: usize, y: usize, state: State<'_, Mutex<GameState>>) -> Result<Vec<Position>, String> {
+    let game = lock_game(&state)?;
+    ensure_board_shown(&game).map_err(|error| error.to_string())?;
+    game.ensure_in_progress().map_err(|error| error.to_string())?;
+    // Empty when the coordinates are out of bounds or the square holds no piece.
+    Ok(game.board().legal_moves(Position::new(x, y)))
+}
+
+
+#[tauri::command]
+pub fn offer_draw(color: Color, app: AppHandle, state: State<'_, Mutex<GameState>>) -> Result<GameState, String> {
+    let mut game = lock_game(&state)?;
+    game.offer_draw(color)?;
+    // The opponent, wherever they are playing from, decides through respond_draw.
+    emit_event(&app, "draw-offered", color);
+    Ok(game.clone())
+}
+
+#[tauri::command]
+pub fn respond_draw(color: Color, accept: bool, app: AppHandle, state: State<'_, Mutex<GameState>>) -> Result<GameState, String> {
+    let mut game = lock_game(&state)?;
+    game.respond_draw(color, accept)?;
+    handle_game_over(&app, &game);
+    Ok(game.clone())
+}
+
+/// Replaces the current game with one rebuilt from a hand-typed move list.
+#[tauri::command]
+pub fn import_scoresheet(text: String, state: State<'_, Mutex<GameState>>) -> Result<ScoresheetImport, String> {
+    let (game, report) = scoresheet::import_scoresheet(&text);
+    *lock_game(&state)? = game;
+    Ok(report)
+}
+
+#[tauri::command]
+pub fn claim_draw(color: Color, app: AppHandle, state: State<'_, Mutex<GameState>>) -> Result<GameState, String> {
+    let mut game = lock_game(&state)?;
+    game.claim_draw(color)?;
+    handle_game_over(&app, &game);
+    Ok(game.clone())
+}
+
+/// Starts a fresh analysis from the position shown on the live board, which is the reviewed one
+/// while browsing the game's history.
+#[tauri::command]
+pub fn start_analysis(state: State<'_, Mutex<GameState>>, analysis: State<'_, Mutex<AnalysisBoard>>) -> Result<AnalysisView, String> {
+    let game = lock_game(&state)?.displayed_position()?;
+    let mut board = lock_analysis(&analysis)?;
+    *board = AnalysisBoard::new(game.board().clone(), game.current_player());
+    Ok(board.view())
+}
+
+#[tauri::command]
+pub fn get_analysis(analysis: State<'_, Mutex<AnalysisBoard>>) -> Result<AnalysisView, String> {
+    Ok(lock_analysis(&analysis)?.view())
+}
+
+#[tauri::command]
+pub fn analysis_move(from: Position, to: Position, promotion: Option<PieceType>, analysis: State<'_, Mutex<AnalysisBoard>>) -> Result<AnalysisView, MoveError> {
+    let mut board = analysis.lock().map_err(|_| MoveError::StateUnavailable)?;
+    board.play(from, to, promotion)?;
+    Ok(board.view())
+}
+
+#[tauri::command]
+pub fn analysis_go_to(node: usize, analysis: State<'_, Mutex<AnalysisBoard>>) -> Result<AnalysisView, String> {
+    let mut board = lock_analysis(&analysis)?;
+    board.go_to(node)?;
+    Ok(board.view())
+}
+
+/// Sets the comment and NAG of the move at 1-based `ply`. The NAG is written as "$n" or as a
+/// symbol such as "!?"; leaving both out clears the move's annotations.
+#[tauri::command]
+pub fn annotate_move(ply: usize, text: Option<String>, nag: Option<String>, state: State<'_, Mutex<GameState>>) -> Result<GameState, String> {
+    let nags = match nag.as_deref().map(str::trim).filter(|nag| !nag.is_empty()) {
+        Some(nag) => vec![notation::parse_nag(nag).ok_or_else(|| format!("\"{}\" is not an annotation glyph", nag))?],
+        None => Vec::new(),
+    };
+    let mut game = lock_game(&state)?;
+    game.annotate_move(ply, text, nags)?;
+    Ok(game.clone())
+}
+
+/// Sets the comment on the game as a whole.
+#[tauri::command]
+pub fn annotate_game(text: Option<String>, state: State<'_, Mutex<GameState>>) -> Result<GameState, String> {
+    let mut game = lock_game(&state)?;
+    game.annotate_game(text);
+    Ok(game.clone())
+}
+
+#[tauri::command]
+pub fn render_move(ply: usize, style: NotationStyle, state: State<'_, Mutex<GameState>>) -> Result<String, String> {
+    lock_game(&state)?.render_move(ply, style).ok_or_else(|| format!("There is no move at ply {}", ply))
+}
+
+#[tauri::command]
+pub fn render_history(style: NotationStyle, state: State<'_, Mutex<GameState>>) -> Result<Vec<String>, String> {
+    Ok(lock_game(&state)?.render_history(style))
+}
+
+/// Starts a new game from a position described in words, e.g. "white: Kg1, pawns f2 g2 h2; black: Kg8".
+#[tauri::command]
+pub fn setup_position(description: String, app: AppHandle, state: State<'_, Mutex<GameState>>) -> Result<GameState, String> {
+    let (board, to_move) = parse_setup(&description)?;
+    let mut game = lock_game(&state)?;
+    game.set_position(board, to_move);
+    emit_event(&app, "turn-changed", game.turn_changed());
+    handle_game_over(&app, &game);
+    Ok(game.clone())
+}
+
+/// Opens the board editor on the current position; moves are refused until editing is finished.
+#[tauri::command]
+pub fn start_editing(state: State<'_, Mutex<GameState>>) -> Result<GameState, String> {
+    let mut game = lock_game(&state)?;
+    game.start_editing();
+    Ok(game.clone())
+}
+
+#[tauri::command]
+pub fn clear_board(state: State<'_, Mutex<GameState>>) -> Result<GameState, String> {
+    let mut game = lock_game(&state)?;
+    game.clear_board()?;
+    Ok(game.clone())
+}
+
+#[tauri::command]
+pub fn place_piece(x: usize, y: usize, piece: Piece, state: State<'_, Mutex<GameState>>) -> Result<GameState, String> {
+    let mut game = lock_game(&state)?;
+    game.place_piece(Position::new(x, y), piece)?;
+    Ok(game.clone())
+}
+
+#[tauri::command]
+pub fn remove_piece(x: usize, y: usize, state: State<'_, Mutex<GameState>>) -> Result<GameState, String> {
+    let mut game = lock_game(&state)?;
+    game.remove_piece(Position::new(x, y))?;
+    Ok(game.clone())
+}
+
+#[tauri::command]
+pub fn set_side_to_move(color: Color, state: State<'_, Mutex<GameState>>) -> Result<GameState, String> {
+    let mut game = lock_game(&state)?;
+    game.set_side_to_move(color)?;
+    Ok(game.clone())
+}
+
+/// Closes the board editor and starts a game from the edited position, or explains why it is illegal.
+#[tauri::command]
+pub fn finish_editing(app: AppHandle, state: State<'_, Mutex<GameState>>) -> Result<GameState, String> {
+    let mut game = lock_game(&state)?;
+    game.finish_editing()?;
+    emit_event(&app, "turn-changed", game.turn_changed());
+    handle_game_over(&app, &game);
+    Ok(game.clone())
+}
+
+/// Reads a file of EPD records, such as a test suite, one position per line.
+#[tauri::command]
+pub fn read_epd_file(path: PathBuf) -> Result<Vec<EpdRecord>, String> {
+    let text = std::fs::read_to_string(&path).map_err(|error| format!("Failed to read {}: {}", path.display(), error))?;
+    EpdRecord::parse_all(&text)
+}
+
+/// Starts a new game from one EPD record, returning the record so its operations can be shown.
+#[tauri::command]
+pub fn load_epd_position(record: String, app: AppHandle, state: State<'_, Mutex<GameState>>) -> Result<EpdRecord, String> {
+    let record = EpdRecord::parse(&record)?;
+    let mut game = lock_game(&state)?;
+    game.set_position(record.board.clone(), record.current_player);
+    emit_event(&app, "turn-changed", game.turn_changed());
+    handle_game_over(&app, &game);
+    Ok(record)
+}
+
+/// Checks a move played in an EPD test position against its `bm` and `am` operations.
+#[tauri::command]
+pub fn check_epd_move(record: String, san: String) -> Result<bool, String> {
+    EpdRecord::parse(&record)?.solves(&san)
+}
+
+#[tauri::command]
+pub fn get_epd(state: State<'_, Mutex<GameState>>) -> Result<String, String> {
+    let game = lock_game(&state)?;
+    Ok(EpdRecord::new(game.board().clone(), game.current_player()).to_epd())
+}
+
+#[tauri::command]
+pub fn get_pgn(state: State<'_, Mutex<GameState>>) -> Result<String, String> {
+    let game = lock_game(&state)?;
+    Ok(pgn::to_pgn(&game))
+}
+
+fn copy_to_clipboard(app: &AppHandle, text: &str) -> Result<(), String> {
+    app.clipboard_manager().write_text(text).map_err(|error| format!("Failed to copy to the clipboard: {}", error))
+}
+
+/// Copies the current position as FEN, returning what was copied.
+#[tauri::command]
+pub fn copy_fen_to_clipboard(app: AppHandle, state: State<'_, Mutex<GameState>>) -> Result<String, String> {
+    let fen = lock_game(&state)?.to_fen();
+    copy_to_clipboard(&app, &fen)?;
+    Ok(fen)
+}
+
+/// Copies the whole game as PGN, returning what was copied.
+#[tauri::command]
+pub fn copy_pgn_to_clipboard(app: AppHandle, state: State<'_, Mutex<GameState>>) -> Result<String, String> {
+    let pgn = get_pgn(state)?;
+    copy_to_clipboard(&app, &pgn)?;
+    Ok(pgn)
+}
+
+/// Writes the game as PGN to `path`, which the frontend gets from the save dialog.
+#[tauri::command]
+pub fn export_pgn(path: String, state: State<'_, Mutex<GameState>>) -> Result<(), String> {
+    let pgn = get_pgn(state)?;
+    std::fs::write(&path, pgn).map_err(|error| format!("Failed to write {}: {}", path, error))
+}
+
+/// Draws the current position as an SVG image in the named board theme, or the chosen one, with
+/// `orientation` at the bottom. The image is also written to `path` when one is given.
+#[tauri::command]
+pub fn render_board_image(theme: Option<String>, orientation: Option<Color>, path: Option<String>, app: AppHandle, state: State<'_, Mutex<GameState>>) -> Result<String, String> {
+    let data_dir = data_dir(&app)?;
+    let theme = theme.unwrap_or_else(|| appearance::load_settings(&data_dir).board_theme);
+    let theme = appearance::list_board_themes(&data_dir).into_iter()
+        .find(|candidate| candidate.name == theme)
+        .ok_or_else(|| format!("There is no board theme named \"{}\"", theme))?;
+    let svg = diagram::render_svg(lock_game(&state)?.board(), orientation.unwrap_or(Color::White), &theme.light, &theme.dark);
+    if let Some(path) = path {
+        std::fs::write(&path, &svg).map_err(|error| format!("Failed to write {}: {}", path, error))?;
+    }
+    Ok(svg)
+}
+
+fn lock_library<'a>(state: &'a State<'_, Mutex<PgnLibrary>>) -> Result<MutexGuard<'a, PgnLibrary>, String> {
+    state.lock().map_err(|_| "Failed to lock PGN library".to_string())
+}
+
+/// Reads a PGN file and lists its games without importing any of them.
+#[tauri::command]
+pub fn preview_pgn(path: String, library: State<'_, Mutex<PgnLibrary>>) -> Result<Vec<PgnPreview>, String> {
+    let text = std::fs::read_to_string(&path).map_err(|error| format!("Failed to read {}: {}", path, error))?;
+    let games = pgn::split_games(&text);
+    let previews = games.iter().enumerate().map(|(index, game)| game.preview(index)).collect();
+    lock_library(&library)?.pending = games;
+    Ok(previews)
+}
+
+/// Imports the chosen games from the last previewed file on a background thread, reporting progress
+/// through "pgn-import-progress" and the outcome through "pgn-import-finished".
+#[tauri::command]
+pub fn import_pgn_games(indices: Vec<usize>, app: AppHandle, library: State<'_, Mutex<PgnLibrary>>) -> Result<(), String> {
+    let selected = {
+        let library = lock_library(&library)?;
+        indices.iter()
+            .map(|&index| library.pending.get(index).cloned().map(|game| (index, game)).ok_or_else(|| format!("There is no game {} in the file", index)))
+            .collect::<Result<Vec<_>, String>>()?
+    };
+
+    std::thread::spawn(move || {
+        let total = selected.len();
+        let mut finished = ImportFinished { imported: 0, failures: Vec::new() };
+        for (done, (index, game)) in selected.into_iter().enumerate() {
+            match game.to_game_state() {
+                Ok(state) => {
+                    let library = app.state::<Mutex<PgnLibrary>>();
+                    let locked = library.lock();
+                    if let Ok(mut library) = locked {
+                        library.imported.push((game.preview(index), state));
+                        finished.imported += 1;
+                    }
+                },
+                Err(error) => finished.failures.push((index, error)),
+            }
+            emit_event(&app, "pgn-import-progress", ImportProgress { done: done + 1, total });
+        }
+        emit_event(&app, "pgn-import-finished", finished);
+    });
+    Ok(())
+}
+
+#[tauri::command]
+pub fn list_imported_games(library: State<'_, Mutex<PgnLibrary>>) -> Result<Vec<PgnPreview>, String> {
+    Ok(lock_library(&library)?.imported.iter().map(|(preview, _)| preview.clone()).collect())
+}
+
+/// Loads one of the imported games onto the main board in review mode; leaving review continues it.
+#[tauri::command]
+pub fn open_imported_game(index: usize, library: State<'_, Mutex<PgnLibrary>>, state: State<'_, Mutex<GameState>>) -> Result<GameState, String> {
+    let imported = lock_library(&library)?.imported.get(index).map(|(_, game)| game.clone());
+    let mut game = imported.ok_or_else(|| format!("There is no imported game {}", index))?;
+    game.review(game.move_history().len())?;
+    *lock_game(&state)? = game.clone();
+    Ok(game)
+}
+
+// Saved games live in their own folder under the app data directory
+fn data_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path_resolver().app_data_dir().ok_or_else(|| "Could not find the app data directory".to_string())
+}
+
+fn saves_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(data_dir(app)?.join("saves"))
+}
+
+#[tauri::command]
+pub fn save_game(name: String, app: AppHandle, state: State<'_, Mutex<GameState>>) -> Result<(), String> {
+    let game = lock_game(&state)?.clone();
+    saves::save_game(&saves_dir(&app)?, &name, &game)
+}
+
+#[tauri::command]
+pub fn load_game(name: String, app: AppHandle, state: State<'_, Mutex<GameState>>) -> Result<GameState, String> {
+    let loaded = saves::load_game(&saves_dir(&app)?, &name)?;
+    let mut game = lock_game(&state)?;
+    *game = loaded;
+    emit_event(&app, "turn-changed", game.turn_changed());
+    Ok(game.clone())
+}
+
+#[tauri::command]
+pub fn list_saved_games(app: AppHandle) -> Result<Vec<SavedGameInfo>, String> {
+    saves::list_saved_games(&saves_dir(&app)?)
+}
+
+fn positions_file(app: &AppHandle) -> Result<PathBuf, String> {
+    Ok(data_dir(app)?.join("positions.json"))
+}
+
+#[tauri::command]
+pub fn list_named_positions(app: AppHandle) -> Result<Vec<NamedPosition>, String> {
+    positions::list_positions(&positions_file(&app)?)
+}
+
+/// Saves a FEN under `name` so games can later be started from it.
+#[tauri::command]
+pub fn save_named_position(name: String, description: String, fen: String, app: AppHandle) -> Result<(), String> {
+    positions::save_position(&positions_file(&app)?, NamedPosition { name, description, fen })
+}
+
+#[tauri::command]
+pub fn delete_named_position(name: String, app: AppHandle) -> Result<(), String> {
+    positions::delete_position(&positions_file(&app)?, &name)
+}
+
+#[tauri::command]
+pub fn start_new_game_from_position(name: String, app: AppHandle, state: State<'_, Mutex<GameState>>) -> Result<GameState, String> {
+    let (board, to_move) = positions::find_position(&positions_file(&app)?, &name)?.board()?;
+    let mut game = lock_game(&state)?;
+    game.set_position(board, to_move);
+    emit_event(&app, "turn-changed", game.turn_changed());
+    handle_game_over(&app, &game);
+    Ok(game.clone())
+}
+
+/// Piece sets to choose from; installed sets come with the folder their images are loaded from.
+#[tauri::command]
+pub fn list_piece_sets(app: AppHandle) -> Result<Vec<PieceSet>, String> {
+    Ok(appearance::list_piece_sets(&data_dir(&app)?))
+}
+
+#[tauri::command]
+pub fn list_board_themes(app: AppHandle) -> Result<Vec<BoardTheme>, String> {
+    Ok(appearance::list_board_themes(&data_dir(&app)?))
+}
+
+#[tauri::command]
+pub fn get_appearance(app: AppHandle) -> Result<AppearanceSettings, String> {
+    Ok(appearance::load_settings(&data_dir(&app)?))
+}
+
+#[tauri::command]
+pub fn set_piece_set(name: String, app: AppHandle) -> Result<AppearanceSettings, String> {
+    appearance::select_piece_set(&data_dir(&app)?, &name)
+}
+
+#[tauri::command]
+pub fn set_board_theme(name: String, app: AppHandle) -> Result<AppearanceSettings, String> {
+    appearance::select_board_theme(&data_dir(&app)?, &name)
+}
+
+/// Turns Kriegspiel on or off, returning the view of the side to move. While it is on, the
+/// commands that show the whole board refuse, and moves are played through `kriegspiel_move`.
+#[tauri::command]
+pub fn set_kriegspiel(enabled: bool, state: State<'_, Mutex<GameState>>) -> Result<KriegspielView, String> {
+    let mut game = lock_game(&state)?;
+    game.set_kriegspiel(enabled);
+    Ok(kriegspiel::player_view(&game))
+}
+
+/// The Kriegspiel view of the side to move: only their own pieces and the referee's announcements.
+#[tauri::command]
+pub fn get_kriegspiel_view(state: State<'_, Mutex<GameState>>) -> Result<KriegspielView, String> {
+    let game = lock_game(&state)?;
+    Ok(kriegspiel::player_view(&game))
+}
+
+/// Plays a move with the game as the Kriegspiel referee. Any refusal is a bare `IllegalMove`, also
+/// announced in the view; otherwise the view of the next player to move is returned.
+#[tauri::command]
+pub fn kriegspiel_move(from: Position, to: Position, promotion: Option<PieceType>, app: AppHandle, state: State<'_, Mutex<GameState>>) -> Result<KriegspielView, MoveError> {
+    let mut game = state.lock().map_err(|_| MoveError::StateUnavailable)?;
+    game.referee_move(from, to, promotion)?;
+    announce_move(&app, &game);
+    Ok(kriegspiel::player_view(&game))
+}
+
+fn lock_database<'a>(state: &'a State<'_, Mutex<GameDatabase>>) -> Result<MutexGuard<'a, GameDatabase>, String> {
+    state.lock().map_err(|_| "Failed to lock game database".to_string())
+}
+
+/// Lists the finished games matching `query`; an empty query lists them all.
+#[tauri::command]
+pub fn search_games(query: GameQuery, database: State<'_, Mutex<GameDatabase>>) -> Result<Vec<StoredGame>, String> {
+    lock_database(&database)?.search(&query)
+}
+
+/// Loads a finished game from the database onto the main board for review.
+#[tauri::command]
+pub fn load_stored_game(id: i64, database: State<'_, Mutex<GameDatabase>>, state: State<'_, Mutex<GameState>>) -> Result<GameState, String> {
+    let mut game = lock_database(&database)?.load(id)?;
+    game.review(game.move_history().len())?;
+    *lock_game(&state)? = game.clone();
+    Ok(game)
+}
+
+/// The PGN of a stored game, e.g. to copy it without loading it over the live game.
+#[tauri::command]
+pub fn get_stored_pgn(id: i64, database: State<'_, Mutex<GameDatabase>>) -> Result<String, String> {
+    lock_database(&database)?.pgn(id)
+}
+
+/// Lists the stored games that reached the position `fen` describes, or the live position if none
+/// is given.
+#[tauri::command]
+pub fn find_games_with_position(fen: Option<String>, database: State<'_, Mutex<GameDatabase>>, state: State<'_, Mutex<GameState>>) -> Result<Vec<StoredGame>, String> {
+    let (board, to_move) = match fen {
+        Some(fen) => ChessBoard::from_fen(&fen)?,
+        None => {
+            let game = lock_game(&state)?;
+            (game.board().clone(), game.current_player())
+        },
+    };
+    lock_database(&database)?.games_with_position(&board, to_move)
+}
+
+/// Summarizes a month of play from the local database, the current month if none is given.
+#[tauri::command]
+pub fn get_monthly_digest(month: Option<String>, database: State<'_, Mutex<GameDatabase>>) -> Result<MonthlyDigest, String> {
+    let month = month.unwrap_or_else(|| chrono::Local::now().format("%Y.%m").to_string());
+    lock_database(&database)?.monthly_digest(&month)
+}
+
+/// Indexes every stored game again, returning how many there are.
+#[tauri::command]
+pub fn rebuild_index(database: State<'_, Mutex<GameDatabase>>) -> Result<usize, String> {
+    lock_database(&database)?.rebuild_index()
+}
+
+/// Runs a script of game commands and assertions (see `script::parse_script`) on the live game,
+/// announcing each step through "script-step" as soon as it has run.
+#[tauri::command]
+pub fn run_script(commands_json: String, app: AppHandle, state: State<'_, Mutex<GameState>>) -> Result<ScriptReport, String> {
+    let steps = script::parse_script(&commands_json)?;
+    let mut game = lock_game(&state)?;
+    let report = script::run_script(&mut game, &steps, |step| emit_event(&app, "script-step", step.clone()));
+    emit_event(&app, "turn-changed", game.turn_changed());
+    Ok(report)
+}
+
+/// Wraps the command handler so every command is written to the session's journal before it runs.
+pub fn journaled<F>(handler: F) -> impl Fn(Invoke) + Send + Sync + 'static
+where
+    F: Fn(Invoke) + Send + Sync + 'static,
+{
+    move |invoke| {
+        let window = invoke.message.window();
+        if let Some(journal) = window.try_state::<Mutex<Journal>>() {
+            match journal.lock() {
+                Ok(mut journal) => journal.record(invoke.message.command(), invoke.message.payload()),
+                Err(_) => eprintln!("Failed to lock the journal"),
+            }
         }
+        handler(invoke)
     }
-    Vec::new() // Return an empty vector if coordinates are out of bounds or square is None.
 }
 
+/// Plays the game commands recorded in a journal again on a new game, which then replaces the live
+/// one, to reproduce what happened in another session.
+#[tauri::command]
+pub fn replay_journal(path: String, app: AppHandle, state: State<'_, Mutex<GameState>>) -> Result<ReplayReport, String> {
+    let (replayed, report) = journal::replay_journal(Path::new(&path))?;
+    let mut game = lock_game(&state)?;
+    *game = replayed;
+    emit_event(&app, "turn-changed", game.turn_changed());
+    Ok(report)
+}
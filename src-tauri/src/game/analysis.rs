@@ -0,0 +1,101 @@
+use serde::Serialize;
+
+use super::board::ChessBoard;
+use super::error::MoveError;
+use super::moves::Move;
+use super::piece::{Color, PieceType, Position};
+use super::utils::generate_move_notation;
+
+/// One position in the analysis tree.
+#[derive(Serialize, Clone, Debug)]
+pub struct AnalysisNode {
+    // The move that led here from the parent, None for the starting position
+    pub san: Option<String>,
+    pub parent: Option<usize>,
+    // The first child is the main line, the others are side variations
+    pub children: Vec<usize>,
+    #[serde(skip)]
+    board: ChessBoard,
+    #[serde(skip)]
+    to_move: Color,
+    #[serde(skip)]
+    played: Option<Move>,
+}
+
+/// What the frontend needs to draw the analysis board: the selected position and the whole tree.
+#[derive(Serialize, Clone, Debug)]
+pub struct AnalysisView {
+    #[serde(flatten)]
+    pub board: ChessBoard,
+    pub current_player: Color,
+    pub current: usize,
+    pub nodes: Vec<AnalysisNode>,
+}
+
+/// A scratch board for exploring lines. Either side can be moved, every new move branches off the
+/// selected position, and none of it touches the live game.
+#[derive(Clone, Debug)]
+pub struct AnalysisBoard {
+    nodes: Vec<AnalysisNode>,
+    current: usize,
+}
+
+impl AnalysisBoard {
+    pub fn new(board: ChessBoard, to_move: Color) -> AnalysisBoard {
+        let root = AnalysisNode { san: None, parent: None, children: Vec::new(), board, to_move, played: None };
+        AnalysisBoard { nodes: vec![root], current: 0 }
+    }
+
+    pub fn view(&self) -> AnalysisView {
+        let node = &self.nodes[self.current];
+        AnalysisView {
+            board: node.board.clone(),
+            current_player: node.to_move,
+            current: self.current,
+            nodes: self.nodes.clone(),
+        }
+    }
+
+    /// Plays a move from the selected position. A move already in the tree is followed rather than
+    /// added twice; anything else starts a new variation.
+    pub fn play(&mut self, from: Position, to: Position, promotion: Option<PieceType>) -> Result<(), MoveError> {
+        let node = &self.nodes[self.current];
+        let piece = node.board.piece_at(from).ok_or(MoveError::PieceMissing)?;
+        if piece.color != node.to_move {
+            return Err(MoveError::NotYourTurn);
+        }
+
+        let mut board = node.board.clone();
+        board.move_piece(from, to, promotion)?;
+        let promoted_to = board.piece_at(to)
+            .map(|moved| moved.piece_type)
+            .filter(|&piece_type| piece.piece_type.is_pawn() && !piece_type.is_pawn());
+        let played = Some(node.board.describe_move(from, to, promoted_to));
+
+        if let Some(&existing) = node.children.iter().find(|&&child| self.nodes[child].played == played) {
+            self.current = existing;
+            return Ok(());
+        }
+
+        let opponent = node.to_move.opposite();
+        let gives_check = board.is_in_check(opponent);
+        let is_checkmate = gives_check && !board.has_legal_moves(opponent);
+        let san = generate_move_notation(&node.board, from, to, promoted_to, gives_check, is_checkmate);
+
+        let index = self.nodes.len();
+        self.nodes.push(AnalysisNode { san: Some(san), parent: Some(self.current), children: Vec::new(), board, to_move: opponent, played });
+        self.nodes[self.current].children.push(index);
+        self.current = index;
+        Ok(())
+    }
+
+    /// Selects another position in the tree.
+    pub fn go_to(&mut self, node: usize) -> Result<(), String> {
+        if node >= self.nodes.len() {
+            return Err("There is no such position in the analysis tree".to_string());
+        }
+
+        self.current = node;
+        Ok(())
+    }
+}
@@ -1,35 +1,594 @@
-use super::{piece::{Piece, Square}, utils::initial_piece_setup};
+use serde::{Deserialize, Serialize};
 
+use super::error::MoveError;
+use super::movement::Movement;
+use super::moves::{Move, MoveType};
+use super::{piece::{Color, Piece, PieceType, Position, Square}, utils::initial_piece_setup};
 
-pub const BOARD_SIZE: usize = 8;
-
-fn create_board() -> [[Option<Piece>; BOARD_SIZE]; BOARD_SIZE] {
-    let mut board = [[None; BOARD_SIZE]; BOARD_SIZE];
 
-    // Initialize the board with empty squares
-    for row in 0..BOARD_SIZE {
-        for col in 0..BOARD_SIZE {
-            board[row][col] = None;
-        }
-    }
+pub const BOARD_SIZE: usize = 8;
 
-    board
+/// Which castling moves each side may still make at some point in the game.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CastlingRights {
+    pub white_kingside: bool,
+    pub white_queenside: bool,
+    pub black_kingside: bool,
+    pub black_queenside: bool,
 }
-struct ChessBoard {
-    board: [[Option<Square>; BOARD_SIZE]; BOARD_SIZE],
+
+/// The 8x8 grid of squares, indexed as `board[y][x]` with black's back rank on row 0, together with
+/// the en passant target left behind by the last move.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ChessBoard {
+    #[serde(rename = "board")]
+    squares: Vec<Vec<Option<Square>>>,
+    // The square a pawn skipped over with its double step on the previous move
+    en_passant: Option<Position>,
 }
 
 impl ChessBoard {
-    fn new() -> Self {
-        let mut board = [[None; BOARD_SIZE]; BOARD_SIZE];  // A 2D array of 8x8
+    pub fn new() -> Self {
+        let mut board = vec![vec![None; BOARD_SIZE]; BOARD_SIZE];
 
-        // Filling the board column by column
-        for col in 0..BOARD_SIZE {
-            for row in 0..BOARD_SIZE {
+        for row in 0..BOARD_SIZE {
+            for col in 0..BOARD_SIZE {
                 board[row][col] = Some(Square::new(col, row, initial_piece_setup(col, row)));
             }
         }
 
-        ChessBoard { board }
+        ChessBoard { squares: board, en_passant: None }
+    }
+
+    /// Builds a board holding only `pieces`, with no en passant target.
+    pub fn from_pieces(pieces: impl IntoIterator<Item = (Position, Piece)>) -> Self {
+        let squares = (0..BOARD_SIZE)
+            .map(|row| (0..BOARD_SIZE).map(|col| Some(Square::new(col, row, None))).collect())
+            .collect();
+        let mut board = ChessBoard { squares, en_passant: None };
+        for (position, piece) in pieces {
+            board.set_piece(position, Some(piece));
+        }
+        board
+    }
+
+    /// Checks that a set-up position could occur in a game with `to_move` to play: one king per side,
+    /// no pawn on the first or last rank, and the side that has just moved not left in check.
+    pub fn validate(&self, to_move: Color) -> Result<(), String> {
+        for color in [Color::White, Color::Black] {
+            let kings = self.pieces().filter(|(_, piece)| piece.piece_type == PieceType::King && piece.color == color).count();
+            if kings != 1 {
+                return Err(format!("{:?} must have exactly one king, not {}", color, kings));
+            }
+        }
+        if let Some((position, _)) = self.pieces().find(|(position, piece)| piece.piece_type.is_pawn() && (position.y == 0 || position.y == BOARD_SIZE - 1)) {
+            return Err(format!("A pawn cannot stand on {}", position.to_algebraic()));
+        }
+        if self.is_in_check(to_move.opposite()) {
+            return Err(format!("{:?} is in check but it is not their move", to_move.opposite()));
+        }
+        Ok(())
+    }
+
+    pub fn square(&self, position: Position) -> Option<&Square> {
+        self.squares.get(position.y).and_then(|row| row.get(position.x)).and_then(|square| square.as_ref())
+    }
+
+    pub fn piece_at(&self, position: Position) -> Option<Piece> {
+        self.square(position).and_then(|square| square.piece())
+    }
+
+    /// Iterates over every piece on the board together with its position.
+    pub fn pieces(&self) -> impl Iterator<Item = (Position, Piece)> + '_ {
+        self.squares.iter().flatten().flatten().filter_map(|square| square.piece().map(|piece| (square.position(), piece)))
+    }
+
+    fn set_piece(&mut self, position: Position, piece: Option<Piece>) {
+        if let Some(Some(square)) = self.squares.get_mut(position.y).and_then(|row| row.get_mut(position.x)) {
+            square.set_piece(piece);
+        }
+    }
+
+    /// Moves the piece on `from` to `to` if that is one of its legal moves, returning the captured piece.
+    /// A pawn reaching the last rank becomes `promotion`, or a queen when none is given.
+    pub fn move_piece(&mut self, from: Position, to: Position, promotion: Option<PieceType>) -> Result<Option<Piece>, MoveError> {
+        let Some(piece) = self.piece_at(from) else {
+            return Err(MoveError::PieceMissing);
+        };
+        if !self.legal_moves(from).contains(&to) {
+            return Err(self.explain_illegal_move(from, to, piece));
+        }
+
+        let promotes = piece.piece_type == PieceType::Pawn && to.y == piece.color.opposite().back_rank();
+        let promotion = match promotion {
+            Some(_) if !promotes => return Err(MoveError::WrongPromotionRank),
+            Some(PieceType::Pawn) | Some(PieceType::King) => return Err(MoveError::InvalidPromotionPiece),
+            Some(piece_type) => Some(piece_type),
+            None if promotes => Some(PieceType::Queen),
+            None => None,
+        };
+
+        Ok(self.apply_move(from, to, promotion))
+    }
+
+    // Works out why `piece` may not go from `from` to `to`
+    fn explain_illegal_move(&self, from: Position, to: Position, piece: Piece) -> MoveError {
+        if self.piece_at(to).is_some_and(|target| target.color == piece.color) {
+            MoveError::DestinationBlocked
+        } else if self.calculate_moves_for(from).contains(&to) {
+            MoveError::WouldLeaveKingInCheck
+        } else {
+            MoveError::UnreachableSquare
+        }
+    }
+
+    /// Returns true if moving `from` to `to` is an en passant capture.
+    pub fn is_en_passant(&self, from: Position, to: Position) -> bool {
+        let Some(pawn) = self.piece_at(from).filter(|piece| piece.piece_type == PieceType::Pawn) else {
+            return false;
+        };
+        let passed_pawn = self.piece_at(Position::new(to.x, from.y));
+
+        from.x != to.x
+            && self.en_passant == Some(to)
+            && self.piece_at(to).is_none()
+            && passed_pawn.is_some_and(|piece| piece.piece_type == PieceType::Pawn && piece.color != pawn.color)
+    }
+
+    // Performs the move without any validation, returning the captured piece.
+    fn apply_move(&mut self, from: Position, to: Position, promotion: Option<PieceType>) -> Option<Piece> {
+        let mut piece = self.piece_at(from)?;
+
+        // A king travelling two files is castling, so the rook jumps to the square it crossed.
+        if piece.piece_type == PieceType::King && from.x.abs_diff(to.x) == 2 {
+            let (rook_from, rook_to) = if to.x > from.x {
+                (Position::new(BOARD_SIZE - 1, from.y), Position::new(to.x - 1, from.y))
+            } else {
+                (Position::new(0, from.y), Position::new(to.x + 1, from.y))
+            };
+            if let Some(mut rook) = self.piece_at(rook_from) {
+                rook.has_moved = true;
+                self.set_piece(rook_from, None);
+                self.set_piece(rook_to, Some(rook));
+            }
+        }
+
+        // En passant takes the pawn beside the mover rather than anything on the target square.
+        let mut captured = self.piece_at(to);
+        if self.is_en_passant(from, to) {
+            let passed_pawn = Position::new(to.x, from.y);
+            captured = self.piece_at(passed_pawn);
+            self.set_piece(passed_pawn, None);
+        }
+
+        self.en_passant = None;
+        if piece.piece_type == PieceType::Pawn && from.y.abs_diff(to.y) == 2 {
+            self.en_passant = Some(Position::new(from.x, (from.y + to.y) / 2));
+        }
+
+        piece.has_moved = true;
+        if let Some(piece_type) = promotion {
+            piece.piece_type = piece_type;
+        }
+        self.set_piece(from, None);
+        self.set_piece(to, Some(piece));
+
+        captured
+    }
+
+    /// Returns the pseudo-legal moves of the piece on `position`, ignoring whether they expose its king.
+    pub fn calculate_moves_for(&self, position: Position) -> Vec<Position> {
+        self.square(position).map_or_else(Vec::new, |square| square.calculate_moves(position, self))
+    }
+
+    /// Returns the moves of the piece on `position` that do not leave its own king in check.
+    pub fn legal_moves(&self, position: Position) -> Vec<Position> {
+        let Some(piece) = self.piece_at(position) else {
+            return Vec::new();
+        };
+
+        // King moves are checked against the attack map directly. The king is lifted off the board
+        // first so that squares behind it on a checking line still count as attacked.
+        if piece.piece_type == PieceType::King {
+            let mut without_king = self.clone();
+            without_king.set_piece(position, None);
+            let attacked = without_king.attacked_squares(piece.color.opposite());
+            return self.calculate_moves_for(position)
+                .into_iter()
+                .filter(|&target| attacked & target.bit() == 0)
+                .collect();
+        }
+
+        let leaves_king_safe = |target: Position| {
+            let mut next = self.clone();
+            next.apply_move(position, target, None);
+            !next.is_in_check(piece.color)
+        };
+
+        // Out of check, a piece can only expose its king by leaving a pin line, so the pin alone decides
+        // its moves. En passant lifts two pawns off a rank and hoppers change their attacks whenever a
+        // screen moves, so those cases still play the move out.
+        let checkers = self.checkers(piece.color);
+        if checkers.len() > 1 {
+            // In double check no single block or capture deals with both attackers, so only the king may move.
+            return Vec::new();
+        }
+        if !checkers.is_empty() || self.has_hoppers(piece.color.opposite()) {
+            return self.calculate_moves_for(position).into_iter().filter(|&target| leaves_king_safe(target)).collect();
+        }
+        let allowed = self.pin_line(position).unwrap_or(u64::MAX);
+        self.calculate_moves_for(position)
+            .into_iter()
+            .filter(|&target| allowed & target.bit() != 0)
+            .filter(|&target| !self.is_en_passant(position, target) || leaves_king_safe(target))
+            .collect()
+    }
+
+    /// If the piece on `position` is pinned to its king, returns the squares it may still move to: the
+    /// line between the king and the pinning piece, the pinner included, as a bit set.
+    pub fn pin_line(&self, position: Position) -> Option<u64> {
+        let piece = self.piece_at(position)?;
+        let king = self.find_king(piece.color)?;
+        let (x_diff, y_diff) = (position.x as i32 - king.x as i32, position.y as i32 - king.y as i32);
+        if (x_diff, y_diff) == (0, 0) || (x_diff != 0 && y_diff != 0 && x_diff.abs() != y_diff.abs()) {
+            return None;
+        }
+        let (dx, dy) = (x_diff.signum(), y_diff.signum());
+
+        // Walk out from the king: the first piece met must be this one, and the next an enemy sliding
+        // back along the same line.
+        let mut line = 0;
+        let mut current = king;
+        while let Some(next) = current.offset(dx, dy) {
+            line |= next.bit();
+            current = next;
+            match self.piece_at(next) {
+                Some(_) if next == position => {},
+                Some(other) => {
+                    let pins = other.color != piece.color
+                        && other.piece_type.movement().iter().any(|movement| movement.slides_along(-dx, -dy));
+                    return pins.then_some(line);
+                },
+                None => {},
+            }
+        }
+        None
+    }
+
+    fn has_hoppers(&self, color: Color) -> bool {
+        self.pieces().any(|(_, piece)| {
+            piece.color == color && piece.piece_type.movement().iter().any(|movement| matches!(movement, Movement::Hopper(_)))
+        })
+    }
+
+    /// Returns true while neither the king nor the rook on the given side has moved or been captured.
+    pub fn has_castling_right(&self, color: Color, kingside: bool) -> bool {
+        let row = color.back_rank();
+        let rook_x = if kingside { BOARD_SIZE - 1 } else { 0 };
+        let unmoved = |x: usize, piece_type: PieceType| {
+            self.piece_at(Position::new(x, row))
+                .is_some_and(|piece| piece.piece_type == piece_type && piece.color == color && !piece.has_moved)
+        };
+
+        unmoved(4, PieceType::King) && unmoved(rook_x, PieceType::Rook)
+    }
+
+    pub fn castling_rights(&self) -> CastlingRights {
+        CastlingRights {
+            white_kingside: self.has_castling_right(Color::White, true),
+            white_queenside: self.has_castling_right(Color::White, false),
+            black_kingside: self.has_castling_right(Color::Black, true),
+            black_queenside: self.has_castling_right(Color::Black, false),
+        }
+    }
+
+    /// Identifies the position for repetition checks: the first four fields of its FEN, with the en
+    /// passant square only listed when a pawn can actually take there.
+    pub fn position_key(&self, to_move: Color) -> String {
+        let placement: Vec<String> = self.squares.iter().map(|row| {
+            let mut rank = String::new();
+            let mut empty = 0;
+            for piece in row.iter().map(|square| square.as_ref().and_then(|square| square.piece())) {
+                let Some(piece) = piece else {
+                    empty += 1;
+                    continue;
+                };
+                if empty > 0 {
+                    rank.push_str(&empty.to_string());
+                    empty = 0;
+                }
+                let symbol = piece.piece_type.symbol();
+                rank.push(if piece.color == Color::White { symbol } else { symbol.to_ascii_lowercase() });
+            }
+            if empty > 0 {
+                rank.push_str(&empty.to_string());
+            }
+            rank
+        }).collect();
+
+        let rights = self.castling_rights();
+        let castling: String = [
+            (rights.white_kingside, 'K'), (rights.white_queenside, 'Q'),
+            (rights.black_kingside, 'k'), (rights.black_queenside, 'q'),
+        ].iter().filter(|(allowed, _)| *allowed).map(|&(_, symbol)| symbol).collect();
+
+        let en_passant = self.en_passant.filter(|&target| {
+            self.pieces().any(|(from, piece)| {
+                piece.color == to_move && self.is_en_passant(from, target) && self.legal_moves(from).contains(&target)
+            })
+        });
+
+        format!(
+            "{} {} {} {}",
+            placement.join("/"),
+            if to_move == Color::White { 'w' } else { 'b' },
+            if castling.is_empty() { "-".to_string() } else { castling },
+            en_passant.map_or_else(|| "-".to_string(), |target| target.to_algebraic()),
+        )
+    }
+
+    /// Describes moving the piece on `from` to `to` in this position, without checking that it is legal.
+    pub fn describe_move(&self, from: Position, to: Position, promotion: Option<PieceType>) -> Move {
+        let piece_type = self.piece_at(from).map(|piece| piece.piece_type);
+        let move_type = match piece_type {
+            Some(PieceType::King) if to.x == from.x + 2 => MoveType::KingsideCastle,
+            Some(PieceType::King) if from.x == to.x + 2 => MoveType::QueensideCastle,
+            Some(PieceType::Pawn) if from.y.abs_diff(to.y) == 2 => MoveType::DoublePawnPush,
+            _ if self.is_en_passant(from, to) => MoveType::EnPassant,
+            _ if self.piece_at(to).is_some() => MoveType::Capture,
+            _ => MoveType::Normal,
+        };
+        Move { from, to, promotion, move_type }
+    }
+
+    /// Writes the position as a FEN string. The board does not track turns, so the side to move and
+    /// the two move counters come from the game.
+    pub fn to_fen(&self, to_move: Color, halfmove_clock: u32, fullmove_number: u32) -> String {
+        format!("{} {} {}", self.position_key(to_move), halfmove_clock, fullmove_number)
+    }
+
+    /// Reads the position and side to move from a FEN, or from the first four fields of one as in EPD.
+    /// Any move counters are ignored. Castling rights are kept by marking kings and rooks that have
+    /// lost them as moved.
+    pub fn from_fen(fen: &str) -> Result<(ChessBoard, Color), String> {
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        let [placement, side, castling, en_passant, ..] = fields[..] else {
+            return Err(format!("\"{}\" is not a FEN: it needs at least four fields", fen));
+        };
+
+        let ranks: Vec<&str> = placement.split('/').collect();
+        if ranks.len() != BOARD_SIZE {
+            return Err(format!("A FEN lists {} ranks, not {}", BOARD_SIZE, ranks.len()));
+        }
+        let mut pieces = Vec::new();
+        for (y, rank) in ranks.iter().enumerate() {
+            let mut x = 0;
+            for symbol in rank.chars() {
+                if let Some(empty) = symbol.to_digit(10) {
+                    x += empty as usize;
+                    continue;
+                }
+                let piece_type = PieceType::from_symbol(symbol.to_ascii_uppercase())
+                    .ok_or_else(|| format!("Unknown piece '{}' in the FEN", symbol))?;
+                let color = if symbol.is_ascii_uppercase() { Color::White } else { Color::Black };
+                pieces.push((Position::new(x, y), Piece::new(piece_type, color)));
+                x += 1;
+            }
+            if x != BOARD_SIZE {
+                return Err(format!("Rank \"{}\" does not cover {} files", rank, BOARD_SIZE));
+            }
+        }
+
+        let to_move = match side {
+            "w" => Color::White,
+            "b" => Color::Black,
+            _ => return Err(format!("Unknown side to move \"{}\"", side)),
+        };
+
+        // A piece has lost its castling rights unless the castling field still lists a right it belongs to.
+        for (position, piece) in pieces.iter_mut() {
+            let rights: &[char] = match (piece.piece_type, piece.color) {
+                (PieceType::King, Color::White) => &['K', 'Q'],
+                (PieceType::King, Color::Black) => &['k', 'q'],
+                (PieceType::Rook, Color::White) if position.x == 0 => &['Q'],
+                (PieceType::Rook, Color::White) => &['K'],
+                (PieceType::Rook, Color::Black) if position.x == 0 => &['q'],
+                (PieceType::Rook, Color::Black) => &['k'],
+                _ => continue,
+            };
+            piece.has_moved = !rights.iter().any(|&right| castling.contains(right));
+        }
+
+        let mut board = ChessBoard::from_pieces(pieces);
+        if en_passant != "-" {
+            let target = Position::from_algebraic(en_passant)
+                .ok_or_else(|| format!("\"{}\" is not an en passant square", en_passant))?;
+            board.en_passant = Some(target);
+        }
+        board.validate(to_move)?;
+        Ok((board, to_move))
+    }
+
+    /// A stable 64-bit FNV-1a hash of `position_key`, cheap to store with every move.
+    pub fn position_hash(&self, to_move: Color) -> u64 {
+        self.position_key(to_move).bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        })
+    }
+
+    /// Every legal move for `color`, listing each promotion choice separately.
+    pub fn all_legal_moves(&self, color: Color) -> Vec<Move> {
+        let mut moves = Vec::new();
+        for (from, piece) in self.pieces().filter(|(_, piece)| piece.color == color) {
+            for to in self.legal_moves(from) {
+                if piece.piece_type.is_pawn() && to.y == color.opposite().back_rank() {
+                    moves.extend(PieceType::PROMOTIONS.iter().map(|&promotion| self.describe_move(from, to, Some(promotion))));
+                } else {
+                    moves.push(self.describe_move(from, to, None));
+                }
+            }
+        }
+        moves
+    }
+
+    pub fn has_legal_moves(&self, color: Color) -> bool {
+        self.pieces().any(|(position, piece)| piece.color == color && !self.legal_moves(position).is_empty())
+    }
+
+    /// Total value of `color`'s pieces on the board (see `PieceType::value`).
+    pub fn material(&self, color: Color) -> u32 {
+        self.pieces().filter(|(_, piece)| piece.color == color).map(|(_, piece)| piece.piece_type.value()).sum()
+    }
+
+    pub fn find_king(&self, color: Color) -> Option<Position> {
+        self.pieces()
+            .find(|(_, piece)| piece.piece_type == PieceType::King && piece.color == color)
+            .map(|(position, _)| position)
+    }
+
+    pub fn is_in_check(&self, color: Color) -> bool {
+        self.find_king(color).is_some_and(|king| self.is_square_attacked(king, color.opposite()))
+    }
+
+    /// Returns every square attacked by `color` as a bit set, one bit per square (see `Position::bit`).
+    pub fn attacked_squares(&self, color: Color) -> u64 {
+        self.pieces()
+            .filter(|(_, piece)| piece.color == color)
+            .fold(0, |attacks, (position, piece)| attacks | self.attacks_from(position, piece))
+    }
+
+    // The squares attacked by `piece` standing on `position`
+    fn attacks_from(&self, position: Position, piece: Piece) -> u64 {
+        let mut attacks = 0;
+        if piece.piece_type.is_pawn() {
+            for dx in [-1, 1] {
+                if let Some(target) = position.offset(dx, piece.color.pawn_direction()) {
+                    attacks |= target.bit();
+                }
+            }
+        }
+        for movement in piece.piece_type.movement() {
+            movement.add_attacks(position, self, &mut attacks);
+        }
+        attacks
+    }
+
+    /// Returns the positions of the enemy pieces giving check to `color`'s king.
+    pub fn checkers(&self, color: Color) -> Vec<Position> {
+        let Some(king) = self.find_king(color) else {
+            return Vec::new();
+        };
+        self.pieces()
+            .filter(|&(position, piece)| piece.color != color && self.attacks_from(position, piece) & king.bit() != 0)
+            .map(|(position, _)| position)
+            .collect()
+    }
+
+    /// Returns true if any piece of color `by` attacks `target`, regardless of what stands on it.
+    /// Cheaper than building the full attack map when only one square matters, as for check tests.
+    pub fn is_square_attacked(&self, target: Position, by: Color) -> bool {
+        // Pawns attack diagonally forward, so look one row behind the target from their point of view.
+        let pawn_attacks = [-1, 1].iter().any(|&dx| {
+            target.offset(dx, -by.pawn_direction())
+                .and_then(|position| self.piece_at(position))
+                .is_some_and(|piece| piece.color == by && piece.piece_type == PieceType::Pawn)
+        });
+        if pawn_attacks {
+            return true;
+        }
+
+        PieceType::ALL.iter().any(|&piece_type| {
+            piece_type.movement().iter().any(|movement| movement.attacks(target, self, piece_type, by))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A square by its algebraic name, e.g. "e4"
+    fn square(name: &str) -> Position {
+        let name = name.as_bytes();
+        Position::new(usize::from(name[0] - b'a'), usize::from(b'8' - name[1]))
+    }
+
+    // The board after playing `moves` from the usual start
+    fn play(moves: &[(&str, &str)]) -> ChessBoard {
+        let mut board = ChessBoard::new();
+        for &(from, to) in moves {
+            board.move_piece(square(from), square(to), None).unwrap();
+        }
+        board
+    }
+
+    fn moves_from(board: &ChessBoard, from: &str) -> Vec<Position> {
+        board.square(square(from)).unwrap().calculate_moves(square(from), board)
     }
-}
\ No newline at end of file
+
+    const OPEN_KINGSIDE: [(&str, &str); 6] = [("e2", "e4"), ("e7", "e5"), ("g1", "f3"), ("b8", "c6"), ("f1", "c4"), ("g8", "f6")];
+
+    #[test]
+    fn castling_brings_the_rook_across() {
+        let mut board = play(&OPEN_KINGSIDE);
+        assert!(moves_from(&board, "e1").contains(&square("g1")));
+        board.move_piece(square("e1"), square("g1"), None).unwrap();
+        assert!(board.piece_at(square("f1")).is_some_and(|piece| piece.piece_type == PieceType::Rook));
+        assert!(board.piece_at(square("h1")).is_none());
+    }
+
+    #[test]
+    fn castling_needs_an_unmoved_rook() {
+        let mut board = play(&OPEN_KINGSIDE);
+        for (from, to) in [("h1", "g1"), ("f6", "g8"), ("g1", "h1")] {
+            board.move_piece(square(from), square(to), None).unwrap();
+        }
+        assert!(!moves_from(&board, "e1").contains(&square("g1")));
+    }
+
+    #[test]
+    fn castling_may_not_pass_through_an_attacked_square() {
+        // The bishop on a6 covers f1.
+        let board = play(&[("e2", "e4"), ("b7", "b6"), ("g1", "f3"), ("c8", "a6"), ("g2", "g3"), ("e7", "e6"), ("f1", "g2")]);
+        assert!(!moves_from(&board, "e1").contains(&square("g1")));
+    }
+
+    #[test]
+    fn en_passant_is_only_legal_right_after_the_double_step() {
+        let mut board = play(&[("e2", "e4"), ("a7", "a6"), ("e4", "e5"), ("d7", "d5")]);
+        assert!(board.legal_moves(square("e5")).contains(&square("d6")));
+        let captured = board.move_piece(square("e5"), square("d6"), None).unwrap();
+        assert!(captured.is_some_and(|piece| piece.piece_type == PieceType::Pawn));
+        assert!(board.piece_at(square("d5")).is_none());
+
+        let board = play(&[("e2", "e4"), ("a7", "a6"), ("e4", "e5"), ("d7", "d5"), ("h2", "h3"), ("h7", "h6")]);
+        assert!(!board.legal_moves(square("e5")).contains(&square("d6")));
+    }
+
+    #[test]
+    fn pinned_pieces_stay_on_the_pin_line() {
+        // The knight on c6 is pinned to the king by the bishop on b5.
+        let board = play(&[("e2", "e4"), ("e7", "e5"), ("g1", "f3"), ("b8", "c6"), ("f1", "b5"), ("d7", "d6")]);
+        assert!(board.legal_moves(square("c6")).is_empty());
+
+        // The bishop blocking the check may slide along the pin line or take the pinning bishop.
+        let board = play(&[("e2", "e4"), ("e7", "e5"), ("g1", "f3"), ("d7", "d6"), ("f1", "b5"), ("c8", "d7")]);
+        let mut moves = board.legal_moves(square("d7"));
+        moves.sort_by_key(|position| (position.y, position.x));
+        assert_eq!(moves, vec![square("c6"), square("b5")]);
+    }
+
+    #[test]
+    fn fen_round_trips() {
+        let fens = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w Kq f6 0 1",
+            "r3k2r/8/8/8/8/8/8/4K2R b Kk - 0 1",
+            "8/8/3k4/8/8/3K4/8/8 w - - 0 1",
+        ];
+        for fen in fens {
+            let (board, to_move) = ChessBoard::from_fen(fen).unwrap();
+            assert_eq!(board.to_fen(to_move, 0, 1), fen);
+        }
+    }
+}
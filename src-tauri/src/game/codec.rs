@@ -0,0 +1,102 @@
+use super::board::ChessBoard;
+use super::moves::Move;
+use super::state::GameState;
+
+/// Encodes the moves of `game` in one byte each: the move's index among the legal moves of the
+/// position it was played in. No position has more than 218 legal moves, so a byte always suffices.
+pub fn encode_moves(game: &GameState) -> Result<Vec<u8>, String> {
+    let mut replay = start_of(game);
+    let mut encoded = Vec::with_capacity(game.move_history().len());
+    for record in game.move_history() {
+        let played = record.played;
+        let index = legal_moves(&replay)
+            .iter()
+            .position(|mv| mv.from == played.from && mv.to == played.to && mv.promotion == played.promotion)
+            .ok_or_else(|| format!("{} is not legal in the position it was played in", record.san))?;
+        encoded.push(u8::try_from(index).map_err(|_| "A position has too many legal moves to encode".to_string())?);
+        replay.move_piece_from(played.from, played.to, played.promotion).map_err(|error| error.to_string())?;
+    }
+    Ok(encoded)
+}
+
+/// Plays moves written by `encode_moves` on `game`, which must be in the position they started from.
+pub fn play_encoded(game: &mut GameState, encoded: &[u8]) -> Result<(), String> {
+    for (ply, &index) in encoded.iter().enumerate() {
+        let played = legal_moves(game).get(usize::from(index)).copied()
+            .ok_or_else(|| format!("Move {} does not decode to a legal move", ply + 1))?;
+        game.move_piece_from(played.from, played.to, played.promotion).map_err(|error| error.to_string())?;
+    }
+    Ok(())
+}
+
+// The legal moves in a fixed order of origin, destination and promotion, so that stored games do
+// not depend on the order move generation happens to produce
+fn legal_moves(game: &GameState) -> Vec<Move> {
+    let mut moves = game.board().all_legal_moves(game.current_player());
+    moves.sort_by_key(|mv| (mv.from.y, mv.from.x, mv.to.y, mv.to.x, mv.promotion.map(|piece_type| piece_type.symbol())));
+    moves
+}
+
+fn start_of(game: &GameState) -> GameState {
+    match game.start_position() {
+        Some((board, to_move)) => GameState::from_position(board.clone(), to_move),
+        None => GameState::new(),
+    }
+}
+
+/// The FEN a game started from, or None for the usual starting position.
+pub fn start_fen(game: &GameState) -> Option<String> {
+    game.start_position().map(|(board, to_move)| board.to_fen(to_move, 0, 1))
+}
+
+/// The game to play encoded moves on: the usual start, or the position `fen` describes.
+pub fn start_from_fen(fen: Option<&str>) -> Result<GameState, String> {
+    match fen {
+        Some(fen) => {
+            let (board, to_move) = ChessBoard::from_fen(fen)?;
+            Ok(GameState::from_position(board, to_move))
+        },
+        None => Ok(GameState::new()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::piece::Position;
+    use crate::game::scoresheet::import_scoresheet;
+
+    fn square(name: &str) -> Position {
+        Position::from_algebraic(name).unwrap()
+    }
+
+    #[test]
+    fn encoded_moves_decode_to_the_same_game() {
+        // Captures, a promotion to a knight and long castling
+        let (game, _) = import_scoresheet("e4 d5 exd5 c6 dxc6 e5 cxb7 Ke7 bxa8=N Nf6 Nb6 axb6 d4 Qd5 Nc3 Qxg2 Bh6 Qxh1 Qe2 gxh6 O-O-O");
+        let encoded = encode_moves(&game).unwrap();
+        assert_eq!(encoded.len(), game.move_history().len());
+
+        let mut decoded = GameState::new();
+        play_encoded(&mut decoded, &encoded).unwrap();
+        assert_eq!(decoded.san_history(), game.san_history());
+        assert_eq!(decoded.to_fen(), game.to_fen());
+    }
+
+    #[test]
+    fn games_from_a_position_decode_from_their_start() {
+        let (board, to_move) = ChessBoard::from_fen("4k3/8/8/8/8/8/8/R3K2R b KQ - 0 1").unwrap();
+        let mut game = GameState::from_position(board, to_move);
+        game.move_piece_from(square("e8"), square("d7"), None).unwrap();
+        game.move_piece_from(square("e1"), square("c1"), None).unwrap();
+
+        let mut decoded = start_from_fen(start_fen(&game).as_deref()).unwrap();
+        play_encoded(&mut decoded, &encode_moves(&game).unwrap()).unwrap();
+        assert_eq!(decoded.to_fen(), game.to_fen());
+    }
+
+    #[test]
+    fn bytes_past_the_legal_moves_are_refused() {
+        assert!(play_encoded(&mut GameState::new(), &[250]).is_err());
+    }
+}
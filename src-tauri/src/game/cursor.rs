@@ -0,0 +1,113 @@
+use serde::{Deserialize, Serialize};
+
+use super::board::BOARD_SIZE;
+use super::error::MoveError;
+use super::piece::{Color, Position};
+use super::state::GameState;
+
+/// A step of the cursor as seen by the player at the bottom of the board.
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+/// What the frontend needs to highlight: the square under the cursor, the picked-up piece and
+/// where it can go.
+#[derive(Serialize, Clone, Debug)]
+pub struct CursorView {
+    pub square: Position,
+    pub selected: Option<Position>,
+    pub targets: Vec<Position>,
+}
+
+/// The cursor after a selection, with the game as it stands, a move having possibly been played.
+#[derive(Serialize, Clone, Debug)]
+pub struct CursorSelection {
+    pub cursor: CursorView,
+    pub game: GameState,
+}
+
+/// A selection cursor for playing without a pointer, e.g. with a gamepad or a TV remote. The
+/// cursor is moved square by square, and selecting picks up a piece and then puts it down.
+#[derive(Clone, Debug)]
+pub struct BoardCursor {
+    square: Position,
+    selected: Option<Position>,
+}
+
+impl BoardCursor {
+    pub fn new() -> BoardCursor {
+        // e2, where most games start
+        BoardCursor { square: Position::new(4, 6), selected: None }
+    }
+
+    /// Moves the cursor one square, stopping at the edge of the board. The board is drawn with
+    /// `orientation` at the bottom, so up is towards the opponent.
+    pub fn step(&mut self, direction: Direction, orientation: Color) {
+        // Row 0 is rank 8, at the top when white sits at the bottom.
+        let (dx, dy) = match direction {
+            Direction::Up => (0, -1),
+            Direction::Down => (0, 1),
+            Direction::Left => (-1, 0),
+            Direction::Right => (1, 0),
+        };
+        let (dx, dy) = match orientation {
+            Color::White => (dx, dy),
+            Color::Black => (-dx, -dy),
+        };
+        let last = BOARD_SIZE as isize - 1;
+        let x = (self.square.x as isize + dx).clamp(0, last);
+        let y = (self.square.y as isize + dy).clamp(0, last);
+        self.square = Position::new(x as usize, y as usize);
+    }
+
+    /// Acts on the square under the cursor. With nothing picked up, one of the mover's pieces is
+    /// picked up; with a piece picked up, selecting one of its targets returns the move to play,
+    /// selecting it again puts it back and selecting another of the mover's pieces switches to it.
+    pub fn select(&mut self, game: &GameState) -> Result<Option<(Position, Position)>, MoveError> {
+        game.ensure_in_progress()?;
+        if let Some(from) = self.selected_piece(game) {
+            if self.square == from {
+                self.selected = None;
+                return Ok(None);
+            }
+            if game.board().legal_moves(from).contains(&self.square) {
+                self.selected = None;
+                return Ok(Some((from, self.square)));
+            }
+            if game.board().piece_at(self.square).is_none_or(|piece| piece.color != game.current_player()) {
+                return Err(MoveError::UnreachableSquare);
+            }
+        }
+
+        let piece = game.board().piece_at(self.square).ok_or(MoveError::PieceMissing)?;
+        if piece.color != game.current_player() {
+            return Err(MoveError::NotYourTurn);
+        }
+        self.selected = Some(self.square);
+        Ok(None)
+    }
+
+    pub fn view(&self, game: &GameState) -> CursorView {
+        let selected = self.selected_piece(game);
+        CursorView {
+            square: self.square,
+            selected,
+            targets: selected.map_or_else(Vec::new, |from| game.board().legal_moves(from)),
+        }
+    }
+
+    // The picked-up piece, if the game has not moved on since, e.g. through an undo or a new game
+    fn selected_piece(&self, game: &GameState) -> Option<Position> {
+        self.selected.filter(|&from| game.board().piece_at(from).is_some_and(|piece| piece.color == game.current_player()))
+    }
+}
+
+impl Default for BoardCursor {
+    fn default() -> Self {
+        BoardCursor::new()
+    }
+}
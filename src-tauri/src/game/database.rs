@@ -0,0 +1,432 @@
+use std::path::Path;
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use super::board::ChessBoard;
+use super::codec;
+use super::pgn::to_pgn;
+use super::piece::Color;
+use super::state::{GameMetadata, GameResult, GameState, PlayerNames, SavedGame};
+
+// Raised whenever the layout of the games table changes
+const SCHEMA_VERSION: i64 = 2;
+
+/// A finished game as listed from the database.
+#[derive(Serialize, Clone, Debug)]
+pub struct StoredGame {
+    pub id: i64,
+    pub white: String,
+    pub black: String,
+    pub result: String,
+    pub date: String,
+    pub opening: Option<String>,
+    pub move_count: usize,
+}
+
+/// Filters for searching the database; every field left out matches all games.
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct GameQuery {
+    // Matched against either player's name
+    pub opponent: Option<String>,
+    pub result: Option<String>,
+    // Inclusive bounds in PGN date format, e.g. "2024.05.18"
+    pub from_date: Option<String>,
+    pub to_date: Option<String>,
+}
+
+/// A summary of one month of play, computed from the stored games alone.
+#[derive(Serialize, Clone, Debug)]
+pub struct MonthlyDigest {
+    // In PGN date format without the day, e.g. "2024.05"
+    pub month: String,
+    pub games_played: usize,
+    // Named openings by how often they were played, the most frequent first
+    pub most_common_openings: Vec<(String, usize)>,
+    pub longest_game: Option<StoredGame>,
+}
+
+// What a stored game holds besides its moves, kept as a small JSON document
+#[derive(Serialize, Deserialize, Default)]
+struct GameDetails {
+    result: Option<GameResult>,
+    metadata: GameMetadata,
+    comment: Option<String>,
+    // 1-based ply of every annotated move, with its comment and NAGs
+    annotations: Vec<(usize, Option<String>, Vec<u8>)>,
+}
+
+/// Finished games kept in a local SQLite file. The searchable headers are columns of their own,
+/// while the moves are packed one byte each (see `codec`) and decoded by replaying them on access,
+/// which keeps even large collections small. Every position reached in a game is indexed by its
+/// hash, so finding the games that reached a position needs no replaying at all.
+pub struct GameDatabase {
+    connection: Connection,
+}
+
+impl GameDatabase {
+    pub fn open(path: &Path) -> Result<GameDatabase, String> {
+        let connection = Connection::open(path).map_err(|error| error.to_string())?;
+        let database = GameDatabase { connection };
+        let version: i64 = database.connection
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .map_err(|error| error.to_string())?;
+        if version < SCHEMA_VERSION {
+            database.migrate(version)?;
+        }
+        Ok(database)
+    }
+
+    /// Stores a finished game played on `date`, returning its id.
+    pub fn store(&self, game: &GameState, date: &str, opening: Option<&str>) -> Result<i64, String> {
+        let transaction = self.connection.unchecked_transaction().map_err(|error| error.to_string())?;
+        let id = self.insert(None, game, date, opening)?;
+        self.index(id, game)?;
+        transaction.commit().map_err(|error| error.to_string())?;
+        Ok(id)
+    }
+
+    /// Lists the games matching `query`, most recent first.
+    pub fn search(&self, query: &GameQuery) -> Result<Vec<StoredGame>, String> {
+        // The name is matched literally, so LIKE's wildcards and the escape character are escaped.
+        let opponent = query.opponent.as_ref().map(|name| {
+            let name = name.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+            format!("%{}%", name)
+        });
+        let mut statement = self.connection.prepare(
+            "SELECT id, white, black, result, date, opening, move_count FROM games
+             WHERE (?1 IS NULL OR white LIKE ?1 ESCAPE '\\' OR black LIKE ?1 ESCAPE '\\')
+               AND (?2 IS NULL OR result = ?2)
+               AND (?3 IS NULL OR date >= ?3)
+               AND (?4 IS NULL OR date <= ?4)
+             ORDER BY date DESC, id DESC",
+        ).map_err(|error| error.to_string())?;
+
+        let rows = statement
+            .query_map(params![opponent, query.result, query.from_date, query.to_date], stored_game)
+            .map_err(|error| error.to_string())?;
+        rows.collect::<Result<_, _>>().map_err(|error| error.to_string())
+    }
+
+    /// Lists the games in which `board` was reached with `to_move` to play, most recent first.
+    pub fn games_with_position(&self, board: &ChessBoard, to_move: Color) -> Result<Vec<StoredGame>, String> {
+        let mut statement = self.connection.prepare(
+            "SELECT id, white, black, result, date, opening, move_count FROM games
+             WHERE id IN (SELECT game_id FROM positions WHERE hash = ?1)
+             ORDER BY date DESC, id DESC",
+        ).map_err(|error| error.to_string())?;
+        let rows = statement
+            .query_map(params![board.position_hash(to_move) as i64], stored_game)
+            .map_err(|error| error.to_string())?;
+        rows.collect::<Result<_, _>>().map_err(|error| error.to_string())
+    }
+
+    /// Summarizes the games played in `month`, given as e.g. "2024.05".
+    pub fn monthly_digest(&self, month: &str) -> Result<MonthlyDigest, String> {
+        let valid = month.len() == 7
+            && month.char_indices().all(|(index, c)| if index == 4 { c == '.' } else { c.is_ascii_digit() });
+        if !valid {
+            return Err(format!("\"{}\" is not a month like 2024.05", month));
+        }
+        let days = format!("{}.%", month);
+
+        let games_played: i64 = self.connection
+            .query_row("SELECT COUNT(*) FROM games WHERE date LIKE ?1", params![days], |row| row.get(0))
+            .map_err(|error| error.to_string())?;
+        let most_common_openings = {
+            let mut statement = self.connection.prepare(
+                "SELECT opening, COUNT(*) FROM games
+                 WHERE date LIKE ?1 AND opening IS NOT NULL
+                 GROUP BY opening ORDER BY COUNT(*) DESC, opening LIMIT 5",
+            ).map_err(|error| error.to_string())?;
+            let rows = statement
+                .query_map(params![days], |row| Ok((row.get(0)?, row.get::<_, i64>(1)? as usize)))
+                .map_err(|error| error.to_string())?;
+            rows.collect::<Result<Vec<_>, _>>().map_err(|error| error.to_string())?
+        };
+        let longest_game = self.connection
+            .query_row(
+                "SELECT id, white, black, result, date, opening, move_count FROM games
+                 WHERE date LIKE ?1 ORDER BY move_count DESC, id LIMIT 1",
+                params![days],
+                stored_game,
+            )
+            .optional()
+            .map_err(|error| error.to_string())?;
+
+        Ok(MonthlyDigest { month: month.to_string(), games_played: games_played as usize, most_common_openings, longest_game })
+    }
+
+    /// Indexes every stored game again from its moves, returning how many games were indexed.
+    pub fn rebuild_index(&self) -> Result<usize, String> {
+        let transaction = self.connection.unchecked_transaction().map_err(|error| error.to_string())?;
+        let count = self.index_all()?;
+        transaction.commit().map_err(|error| error.to_string())?;
+        Ok(count)
+    }
+
+    /// Exports a stored game as PGN.
+    pub fn pgn(&self, id: i64) -> Result<String, String> {
+        Ok(to_pgn(&self.load(id)?))
+    }
+
+    /// Rebuilds a stored game so it can be reviewed.
+    pub fn load(&self, id: i64) -> Result<GameState, String> {
+        let (white, black, start, moves, details): (String, String, Option<String>, Vec<u8>, String) = self.query(
+            id,
+            "SELECT white, black, start, moves, details FROM games WHERE id = ?1",
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)),
+        )?;
+        let details: GameDetails = serde_json::from_str(&details).map_err(|error| error.to_string())?;
+
+        let mut game = codec::start_from_fen(start.as_deref())?;
+        codec::play_encoded(&mut game, &moves)?;
+        game.set_player_names(PlayerNames { white, black });
+        game.set_metadata(details.metadata);
+        game.annotate_game(details.comment);
+        for (ply, comment, nags) in details.annotations {
+            game.annotate_move(ply, comment, nags)?;
+        }
+        if let Some(result) = details.result.filter(|_| !game.is_game_over()) {
+            game.adjudicate(result)?;
+        }
+        Ok(game)
+    }
+
+    fn insert(&self, id: Option<i64>, game: &GameState, date: &str, opening: Option<&str>) -> Result<i64, String> {
+        let result = game.result().ok_or("Only finished games are stored")?;
+        let details = GameDetails {
+            result: Some(result),
+            metadata: game.metadata().clone(),
+            comment: game.comment().map(str::to_string),
+            annotations: game.move_history().iter().enumerate()
+                .filter(|(_, record)| record.comment.is_some() || !record.nags.is_empty())
+                .map(|(index, record)| (index + 1, record.comment.clone(), record.nags.clone()))
+                .collect(),
+        };
+        let details = serde_json::to_string(&details).map_err(|error| error.to_string())?;
+        self.connection.execute(
+            "INSERT INTO games (id, white, black, result, date, opening, move_count, start, moves, details)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                id,
+                game.players().white,
+                game.players().black,
+                result.score(),
+                date,
+                opening,
+                game.move_history().len() as i64,
+                codec::start_fen(game),
+                codec::encode_moves(game)?,
+                details,
+            ],
+        ).map_err(|error| error.to_string())?;
+        Ok(self.connection.last_insert_rowid())
+    }
+
+    // Records every position reached in the game, the starting one included
+    fn index(&self, id: i64, game: &GameState) -> Result<(), String> {
+        let start = match game.start_position() {
+            Some((board, to_move)) => board.position_hash(to_move),
+            None => ChessBoard::new().position_hash(Color::White),
+        };
+        let hashes = std::iter::once(start).chain(game.move_history().iter().map(|record| record.position_hash));
+        let mut statement = self.connection
+            .prepare_cached("INSERT OR IGNORE INTO positions (hash, game_id) VALUES (?1, ?2)")
+            .map_err(|error| error.to_string())?;
+        for hash in hashes {
+            // SQLite integers are signed, so the hash is stored with its bits unchanged.
+            statement.execute(params![hash as i64, id]).map_err(|error| error.to_string())?;
+        }
+        Ok(())
+    }
+
+    fn index_all(&self) -> Result<usize, String> {
+        self.connection.execute_batch("DELETE FROM positions;").map_err(|error| error.to_string())?;
+        let ids = {
+            let mut statement = self.connection.prepare("SELECT id FROM games").map_err(|error| error.to_string())?;
+            let rows = statement.query_map([], |row| row.get::<_, i64>(0)).map_err(|error| error.to_string())?;
+            rows.collect::<Result<Vec<_>, _>>().map_err(|error| error.to_string())?
+        };
+        for &id in &ids {
+            self.index(id, &self.load(id)?)?;
+        }
+        Ok(ids.len())
+    }
+
+    // Brings the tables from `version` up to date, one layout change at a time
+    fn migrate(&self, version: i64) -> Result<(), String> {
+        let transaction = self.connection.unchecked_transaction().map_err(|error| error.to_string())?;
+        if version < 1 {
+            self.create_games_table()?;
+        }
+        if version < 2 {
+            self.connection.execute_batch(
+                "CREATE TABLE positions (
+                    hash INTEGER NOT NULL,
+                    game_id INTEGER NOT NULL REFERENCES games (id),
+                    PRIMARY KEY (hash, game_id)
+                ) WITHOUT ROWID;",
+            ).map_err(|error| error.to_string())?;
+            self.index_all()?;
+        }
+        self.connection
+            .execute_batch(&format!("PRAGMA user_version = {};", SCHEMA_VERSION))
+            .map_err(|error| error.to_string())?;
+        transaction.commit().map_err(|error| error.to_string())
+    }
+
+    // Creates the games table, re-encoding the games of an older layout that kept every game as
+    // PGN plus a JSON saved game
+    fn create_games_table(&self) -> Result<(), String> {
+        let has_games: bool = self.connection
+            .query_row("SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'games'", [], |row| row.get(0))
+            .map_err(|error| error.to_string())?;
+        if has_games {
+            self.connection.execute_batch("ALTER TABLE games RENAME TO old_games;").map_err(|error| error.to_string())?;
+        }
+        self.connection.execute_batch(
+            "CREATE TABLE games (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                white TEXT NOT NULL,
+                black TEXT NOT NULL,
+                result TEXT NOT NULL,
+                date TEXT NOT NULL,
+                opening TEXT,
+                move_count INTEGER NOT NULL,
+                start TEXT,
+                moves BLOB NOT NULL,
+                details TEXT NOT NULL
+            );",
+        ).map_err(|error| error.to_string())?;
+
+        if has_games {
+            let old_games = {
+                let mut statement = self.connection
+                    .prepare("SELECT id, date, opening, saved FROM old_games")
+                    .map_err(|error| error.to_string())?;
+                let rows = statement.query_map([], |row| {
+                    Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get::<_, Option<String>>(2)?, row.get::<_, String>(3)?))
+                }).map_err(|error| error.to_string())?;
+                rows.collect::<Result<Vec<_>, _>>().map_err(|error| error.to_string())?
+            };
+            for (id, date, opening, saved) in old_games {
+                let saved: SavedGame = serde_json::from_str(&saved).map_err(|error| error.to_string())?;
+                self.insert(Some(id), &GameState::from_saved(saved)?, &date, opening.as_deref())?;
+            }
+            self.connection.execute_batch("DROP TABLE old_games;").map_err(|error| error.to_string())?;
+        }
+        Ok(())
+    }
+
+    fn query<T>(&self, id: i64, sql: &str, read: impl FnOnce(&rusqlite::Row<'_>) -> rusqlite::Result<T>) -> Result<T, String> {
+        self.connection
+            .query_row(sql, params![id], read)
+            .optional()
+            .map_err(|error| error.to_string())?
+            .ok_or_else(|| format!("There is no stored game {}", id))
+    }
+}
+
+fn stored_game(row: &rusqlite::Row<'_>) -> rusqlite::Result<StoredGame> {
+    Ok(StoredGame {
+        id: row.get(0)?,
+        white: row.get(1)?,
+        black: row.get(2)?,
+        result: row.get(3)?,
+        date: row.get(4)?,
+        opening: row.get(5)?,
+        move_count: row.get::<_, i64>(6)? as usize,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::game::piece::Position;
+
+    // A fresh database file for one test, removed before use in case an earlier run left it behind
+    fn database_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("chess-{}-{}.sqlite", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    fn scholars_mate() -> GameState {
+        let mut game = GameState::new();
+        for (from, to) in [("e2", "e4"), ("e7", "e5"), ("f1", "c4"), ("b8", "c6"), ("d1", "h5"), ("g8", "f6"), ("h5", "f7")] {
+            game.move_piece_from(Position::from_algebraic(from).unwrap(), Position::from_algebraic(to).unwrap(), None).unwrap();
+        }
+        game
+    }
+
+    #[test]
+    fn stored_games_load_unchanged() {
+        let path = database_path("store");
+        let database = GameDatabase::open(&path).unwrap();
+        let mut game = scholars_mate();
+        game.set_player_names(PlayerNames { white: "Alice".to_string(), black: "Bob".to_string() });
+        game.annotate_move(7, Some("Mate".to_string()), Vec::new()).unwrap();
+        let id = database.store(&game, &game.metadata().date, None).unwrap();
+        assert!(database.store(&GameState::new(), "2024.01.01", None).is_err());
+
+        let loaded = database.load(id).unwrap();
+        assert_eq!(loaded.san_history(), game.san_history());
+        assert_eq!(loaded.result(), game.result());
+        assert_eq!(loaded.metadata().date, game.metadata().date);
+        assert_eq!(loaded.move_history()[6].comment.as_deref(), Some("Mate"));
+        assert!(database.pgn(id).unwrap().contains(&format!("[Date \"{}\"]", game.metadata().date)));
+
+        let query = GameQuery { opponent: Some("ali".to_string()), ..GameQuery::default() };
+        assert_eq!(database.search(&query).unwrap().iter().map(|game| game.id).collect::<Vec<_>>(), vec![id]);
+        // Wildcards in the name are matched literally.
+        for opponent in ["A_ice", "%", "\\"] {
+            let query = GameQuery { opponent: Some(opponent.to_string()), ..GameQuery::default() };
+            assert!(database.search(&query).unwrap().is_empty());
+        }
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn version_one_databases_get_a_position_index() {
+        let path = database_path("v1");
+        let id = GameDatabase::open(&path).unwrap().store(&scholars_mate(), "2024.01.01", None).unwrap();
+        {
+            let connection = Connection::open(&path).unwrap();
+            connection.execute_batch("DROP TABLE positions; PRAGMA user_version = 1;").unwrap();
+        }
+
+        let database = GameDatabase::open(&path).unwrap();
+        let (board, to_move) = ChessBoard::from_fen("rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2").unwrap();
+        let found = database.games_with_position(&board, to_move).unwrap();
+        assert_eq!(found.iter().map(|game| game.id).collect::<Vec<_>>(), vec![id]);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn pgn_layout_games_are_re_encoded() {
+        let path = database_path("v0");
+        let game = scholars_mate();
+        {
+            let connection = Connection::open(&path).unwrap();
+            connection.execute_batch(
+                "CREATE TABLE games (id INTEGER PRIMARY KEY AUTOINCREMENT, white TEXT NOT NULL, black TEXT NOT NULL, result TEXT NOT NULL, date TEXT NOT NULL, opening TEXT, move_count INTEGER NOT NULL, pgn TEXT NOT NULL, saved TEXT NOT NULL);",
+            ).unwrap();
+            connection.execute(
+                "INSERT INTO games (id, white, black, result, date, opening, move_count, pgn, saved) VALUES (7, '', '', '1-0', '2024.01.01', NULL, 7, '', ?1)",
+                params![serde_json::to_string(&game.to_saved()).unwrap()],
+            ).unwrap();
+        }
+
+        let database = GameDatabase::open(&path).unwrap();
+        let stored = database.search(&GameQuery::default()).unwrap();
+        assert_eq!(stored.iter().map(|game| game.id).collect::<Vec<_>>(), vec![7]);
+        let loaded = database.load(7).unwrap();
+        assert_eq!(loaded.san_history(), game.san_history());
+        assert_eq!(loaded.result(), game.result());
+        assert_eq!(database.games_with_position(&ChessBoard::new(), Color::White).unwrap().len(), 1);
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,59 @@
+use super::board::{ChessBoard, BOARD_SIZE};
+use super::notation::figurine;
+use super::piece::{Color, Position};
+
+const SQUARE_SIZE: usize = 45;
+
+/// Draws the board as an SVG diagram with `orientation` at the bottom, using `light` and `dark` as
+/// the square colors. Pieces are drawn with the chess figurine characters, so no images are needed.
+pub fn render_svg(board: &ChessBoard, orientation: Color, light: &str, dark: &str) -> String {
+    let size = SQUARE_SIZE * BOARD_SIZE;
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{size}\" height=\"{size}\" viewBox=\"0 0 {size} {size}\">\n"
+    );
+
+    for y in 0..BOARD_SIZE {
+        for x in 0..BOARD_SIZE {
+            // Row 0 is rank 8, which is at the top when white sits at the bottom.
+            let (column, row) = match orientation {
+                Color::White => (x, y),
+                Color::Black => (BOARD_SIZE - 1 - x, BOARD_SIZE - 1 - y),
+            };
+            let (left, top) = (column * SQUARE_SIZE, row * SQUARE_SIZE);
+            let (fill, other) = [(light, dark), (dark, light)][(x + y) % 2];
+            svg.push_str(&format!(
+                "<rect x=\"{left}\" y=\"{top}\" width=\"{SQUARE_SIZE}\" height=\"{SQUARE_SIZE}\" fill=\"{fill}\"/>\n"
+            ));
+
+            let position = Position::new(x, y);
+            // Files are labelled along the bottom edge and ranks along the left one.
+            if row == BOARD_SIZE - 1 {
+                svg.push_str(&format!(
+                    "<text x=\"{}\" y=\"{}\" font-size=\"10\" font-family=\"sans-serif\" text-anchor=\"end\" fill=\"{other}\">{}</text>\n",
+                    left + SQUARE_SIZE - 2, top + SQUARE_SIZE - 3, position.file()
+                ));
+            }
+            if column == 0 {
+                svg.push_str(&format!(
+                    "<text x=\"{}\" y=\"{}\" font-size=\"10\" font-family=\"sans-serif\" fill=\"{other}\">{}</text>\n",
+                    left + 2, top + 11, position.rank()
+                ));
+            }
+
+            if let Some(piece) = board.piece_at(position) {
+                // The solid figurines are used for both sides and filled in each side's color.
+                let (fill, stroke) = match piece.color {
+                    Color::White => ("#ffffff", "#000000"),
+                    Color::Black => ("#000000", "#000000"),
+                };
+                svg.push_str(&format!(
+                    "<text x=\"{}\" y=\"{}\" font-size=\"36\" text-anchor=\"middle\" dominant-baseline=\"central\" fill=\"{fill}\" stroke=\"{stroke}\" stroke-width=\"1\">{}</text>\n",
+                    left + SQUARE_SIZE / 2, top + SQUARE_SIZE / 2, figurine(piece.piece_type, Color::Black)
+                ));
+            }
+        }
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
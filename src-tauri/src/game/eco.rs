@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::OnceLock;
+
+use serde::{Deserialize, Serialize};
+
+use super::board::ChessBoard;
+use super::piece::Color;
+use super::utils::generate_move_notation;
+
+/// An opening as classified by the Encyclopaedia of Chess Openings.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Opening {
+    pub eco: String,
+    pub name: String,
+}
+
+impl fmt::Display for Opening {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.eco, self.name)
+    }
+}
+
+// ECO code, name and the moves that reach the opening from the starting position, without check marks
+const OPENINGS: &[(&str, &str, &str)] = &[
+    ("A00", "Polish Opening", "b4"),
+    ("A00", "Grob Opening", "g4"),
+    ("A01", "Nimzowitsch-Larsen Attack", "b3"),
+    ("A02", "Bird's Opening", "f4"),
+    ("A04", "Zukertort Opening", "Nf3"),
+    ("A09", "Réti Opening", "Nf3 d5 c4"),
+    ("A10", "English Opening", "c4"),
+    ("A15", "English Opening: Anglo-Indian Defense", "c4 Nf6"),
+    ("A20", "English Opening: King's English Variation", "c4 e5"),
+    ("A30", "English Opening: Symmetrical Variation", "c4 c5"),
+    ("A40", "Queen's Pawn Game", "d4"),
+    ("A43", "Benoni Defense: Old Benoni", "d4 c5"),
+    ("A45", "Indian Defense", "d4 Nf6"),
+    ("A56", "Benoni Defense", "d4 Nf6 c4 c5"),
+    ("A57", "Benko Gambit", "d4 Nf6 c4 c5 d5 b5"),
+    ("A60", "Benoni Defense: Modern Variation", "d4 Nf6 c4 c5 d5 e6"),
+    ("A80", "Dutch Defense", "d4 f5"),
+    ("B00", "Nimzowitsch Defense", "e4 Nc6"),
+    ("B01", "Scandinavian Defense", "e4 d5"),
+    ("B02", "Alekhine Defense", "e4 Nf6"),
+    ("B06", "Modern Defense", "e4 g6"),
+    ("B07", "Pirc Defense", "e4 d6 d4 Nf6 Nc3 g6"),
+    ("B10", "Caro-Kann Defense", "e4 c6"),
+    ("B12", "Caro-Kann Defense: Advance Variation", "e4 c6 d4 d5 e5"),
+    ("B13", "Caro-Kann Defense: Exchange Variation", "e4 c6 d4 d5 exd5 cxd5"),
+    ("B18", "Caro-Kann Defense: Classical Variation", "e4 c6 d4 d5 Nc3 dxe4 Nxe4 Bf5"),
+    ("B20", "Sicilian Defense", "e4 c5"),
+    ("B21", "Sicilian Defense: Smith-Morra Gambit", "e4 c5 d4 cxd4 c3"),
+    ("B22", "Sicilian Defense: Alapin Variation", "e4 c5 c3"),
+    ("B23", "Sicilian Defense: Closed", "e4 c5 Nc3"),
+    ("B30", "Sicilian Defense: Old Sicilian", "e4 c5 Nf3 Nc6"),
+    ("B33", "Sicilian Defense: Sveshnikov Variation", "e4 c5 Nf3 Nc6 d4 cxd4 Nxd4 Nf6 Nc3 e5"),
+    ("B34", "Sicilian Defense: Accelerated Dragon", "e4 c5 Nf3 Nc6 d4 cxd4 Nxd4 g6"),
+    ("B40", "Sicilian Defense: French Variation", "e4 c5 Nf3 e6"),
+    ("B44", "Sicilian Defense: Taimanov Variation", "e4 c5 Nf3 e6 d4 cxd4 Nxd4 Nc6"),
+    ("B50", "Sicilian Defense", "e4 c5 Nf3 d6"),
+    ("B51", "Sicilian Defense: Moscow Variation", "e4 c5 Nf3 d6 Bb5"),
+    ("B54", "Sicilian Defense: Open", "e4 c5 Nf3 d6 d4 cxd4 Nxd4"),
+    ("B56", "Sicilian Defense: Classical Variation", "e4 c5 Nf3 d6 d4 cxd4 Nxd4 Nf6 Nc3 Nc6"),
+    ("B70", "Sicilian Defense: Dragon Variation", "e4 c5 Nf3 d6 d4 cxd4 Nxd4 Nf6 Nc3 g6"),
+    ("B80", "Sicilian Defense: Scheveningen Variation", "e4 c5 Nf3 d6 d4 cxd4 Nxd4 Nf6 Nc3 e6"),
+    ("B90", "Sicilian Defense: Najdorf Variation", "e4 c5 Nf3 d6 d4 cxd4 Nxd4 Nf6 Nc3 a6"),
+    ("C00", "French Defense", "e4 e6"),
+    ("C01", "French Defense: Exchange Variation", "e4 e6 d4 d5 exd5"),
+    ("C02", "French Defense: Advance Variation", "e4 e6 d4 d5 e5"),
+    ("C03", "French Defense: Tarrasch Variation", "e4 e6 d4 d5 Nd2"),
+    ("C10", "French Defense: Paulsen Variation", "e4 e6 d4 d5 Nc3"),
+    ("C11", "French Defense: Classical Variation", "e4 e6 d4 d5 Nc3 Nf6"),
+    ("C15", "French Defense: Winawer Variation", "e4 e6 d4 d5 Nc3 Bb4"),
+    ("C20", "King's Pawn Game", "e4 e5"),
+    ("C21", "Center Game", "e4 e5 d4 exd4"),
+    ("C21", "Danish Gambit", "e4 e5 d4 exd4 c3"),
+    ("C23", "Bishop's Opening", "e4 e5 Bc4"),
+    ("C25", "Vienna Game", "e4 e5 Nc3"),
+    ("C30", "King's Gambit", "e4 e5 f4"),
+    ("C33", "King's Gambit Accepted", "e4 e5 f4 exf4"),
+    ("C40", "King's Knight Opening", "e4 e5 Nf3"),
+    ("C41", "Philidor Defense", "e4 e5 Nf3 d6"),
+    ("C42", "Petrov's Defense", "e4 e5 Nf3 Nf6"),
+    ("C44", "King's Knight Opening: Normal Variation", "e4 e5 Nf3 Nc6"),
+    ("C44", "Scotch Game", "e4 e5 Nf3 Nc6 d4"),
+    ("C45", "Scotch Game", "e4 e5 Nf3 Nc6 d4 exd4 Nxd4"),
+    ("C46", "Three Knights Opening", "e4 e5 Nf3 Nc6 Nc3"),
+    ("C47", "Four Knights Game", "e4 e5 Nf3 Nc6 Nc3 Nf6"),
+    ("C50", "Italian Game", "e4 e5 Nf3 Nc6 Bc4"),
+    ("C50", "Italian Game: Giuoco Piano", "e4 e5 Nf3 Nc6 Bc4 Bc5"),
+    ("C51", "Italian Game: Evans Gambit", "e4 e5 Nf3 Nc6 Bc4 Bc5 b4"),
+    ("C55", "Italian Game: Two Knights Defense", "e4 e5 Nf3 Nc6 Bc4 Nf6"),
+    ("C57", "Italian Game: Two Knights Defense, Knight Attack", "e4 e5 Nf3 Nc6 Bc4 Nf6 Ng5"),
+    ("C60", "Ruy Lopez", "e4 e5 Nf3 Nc6 Bb5"),
+    ("C65", "Ruy Lopez: Berlin Defense", "e4 e5 Nf3 Nc6 Bb5 Nf6"),
+    ("C68", "Ruy Lopez: Exchange Variation", "e4 e5 Nf3 Nc6 Bb5 a6 Bxc6"),
+    ("C70", "Ruy Lopez: Morphy Defense", "e4 e5 Nf3 Nc6 Bb5 a6 Ba4"),
+    ("C84", "Ruy Lopez: Closed", "e4 e5 Nf3 Nc6 Bb5 a6 Ba4 Nf6 O-O Be7"),
+    ("C89", "Ruy Lopez: Marshall Attack", "e4 e5 Nf3 Nc6 Bb5 a6 Ba4 Nf6 O-O Be7 Re1 b5 Bb3 O-O c3 d5"),
+    ("D00", "Queen's Pawn Game", "d4 d5"),
+    ("D02", "Queen's Pawn Game: London System", "d4 d5 Nf3 Nf6 Bf4"),
+    ("D06", "Queen's Gambit", "d4 d5 c4"),
+    ("D07", "Queen's Gambit Declined: Chigorin Defense", "d4 d5 c4 Nc6"),
+    ("D08", "Queen's Gambit Declined: Albin Countergambit", "d4 d5 c4 e5"),
+    ("D10", "Slav Defense", "d4 d5 c4 c6"),
+    ("D20", "Queen's Gambit Accepted", "d4 d5 c4 dxc4"),
+    ("D30", "Queen's Gambit Declined", "d4 d5 c4 e6"),
+    ("D35", "Queen's Gambit Declined: Exchange Variation", "d4 d5 c4 e6 Nc3 Nf6 cxd5"),
+    ("D43", "Semi-Slav Defense", "d4 d5 c4 c6 Nf3 Nf6 Nc3 e6"),
+    ("D80", "Grünfeld Defense", "d4 Nf6 c4 g6 Nc3 d5"),
+    ("D85", "Grünfeld Defense: Exchange Variation", "d4 Nf6 c4 g6 Nc3 d5 cxd5 Nxd5"),
+    ("E01", "Catalan Opening", "d4 Nf6 c4 e6 g3"),
+    ("E11", "Bogo-Indian Defense", "d4 Nf6 c4 e6 Nf3 Bb4"),
+    ("E12", "Queen's Indian Defense", "d4 Nf6 c4 e6 Nf3 b6"),
+    ("E20", "Nimzo-Indian Defense", "d4 Nf6 c4 e6 Nc3 Bb4"),
+    ("E60", "King's Indian Defense", "d4 Nf6 c4 g6"),
+    ("E80", "King's Indian Defense: Sämisch Variation", "d4 Nf6 c4 g6 Nc3 Bg7 e4 d6 f3"),
+];
+
+/// The opening reached by a game whose position keys are `positions`, oldest first. The latest
+/// position found in the table wins, so transpositions are recognized and the name stays once
+/// the game leaves the book.
+pub fn classify(positions: &[String]) -> Option<Opening> {
+    let table = table();
+    positions.iter().rev().find_map(|key| table.get(key)).map(|&(eco, name)| Opening {
+        eco: eco.to_string(),
+        name: name.to_string(),
+    })
+}
+
+// Position key reached by each opening's moves, built the first time an opening is looked up
+fn table() -> &'static HashMap<String, (&'static str, &'static str)> {
+    static TABLE: OnceLock<HashMap<String, (&'static str, &'static str)>> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        OPENINGS.iter()
+            .filter_map(|&(eco, name, moves)| Some((play_line(moves)?, (eco, name))))
+            .collect()
+    })
+}
+
+// Plays a line of SAN moves from the starting position, returning the key of the position it reaches
+fn play_line(moves: &str) -> Option<String> {
+    let mut board = ChessBoard::new();
+    let mut to_move = Color::White;
+    for san in moves.split_whitespace() {
+        let played = board.all_legal_moves(to_move).into_iter()
+            .find(|mv| generate_move_notation(&board, mv.from, mv.to, mv.promotion, false, false) == san)?;
+        board.move_piece(played.from, played.to, played.promotion).ok()?;
+        to_move = to_move.opposite();
+    }
+    Some(board.position_key(to_move))
+}
@@ -0,0 +1,147 @@
+use serde::Serialize;
+
+use super::board::ChessBoard;
+use super::piece::Color;
+use super::utils::generate_move_notation;
+
+/// One EPD record: a position without move counters, followed by opcodes such as
+/// `bm Qxf7+; id "WAC.001";`.
+#[derive(Serialize, Clone, Debug)]
+pub struct EpdRecord {
+    #[serde(flatten)]
+    pub board: ChessBoard,
+    pub current_player: Color,
+    // Each opcode with its operands, in the order they were written
+    pub operations: Vec<(String, Vec<String>)>,
+}
+
+impl EpdRecord {
+    pub fn new(board: ChessBoard, current_player: Color) -> Self {
+        EpdRecord { board, current_player, operations: Vec::new() }
+    }
+
+    /// Reads one EPD line, such as a position from the WAC test suite.
+    pub fn parse(line: &str) -> Result<EpdRecord, String> {
+        let line = line.trim();
+        // The four position fields come first; everything after them is operations.
+        let mut rest = line;
+        for _ in 0..4 {
+            rest = rest.trim_start();
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            rest = &rest[end..];
+        }
+        let position = &line[..line.len() - rest.len()];
+        let (board, current_player) = ChessBoard::from_fen(position)?;
+
+        let mut operations = Vec::new();
+        for operation in split_operations(rest)? {
+            let mut words = operands(&operation).into_iter();
+            if let Some(opcode) = words.next() {
+                operations.push((opcode, words.collect()));
+            }
+        }
+        Ok(EpdRecord { board, current_player, operations })
+    }
+
+    /// Reads every record in `text`, one per line, skipping blank lines.
+    pub fn parse_all(text: &str) -> Result<Vec<EpdRecord>, String> {
+        text.lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(index, line)| EpdRecord::parse(line).map_err(|error| format!("Line {}: {}", index + 1, error)))
+            .collect()
+    }
+
+    pub fn to_epd(&self) -> String {
+        // position_key is the first four FEN fields, which is exactly an EPD position.
+        let mut epd = self.board.position_key(self.current_player);
+        for (opcode, operands) in &self.operations {
+            epd.push(' ');
+            epd.push_str(opcode);
+            for operand in operands {
+                epd.push(' ');
+                if is_string_opcode(opcode) || operand.contains(char::is_whitespace) || operand.is_empty() {
+                    epd.push_str(&format!("\"{}\"", operand));
+                } else {
+                    epd.push_str(operand);
+                }
+            }
+            epd.push(';');
+        }
+        epd
+    }
+
+    /// The operands of `opcode`, if the record has it.
+    pub fn operation(&self, opcode: &str) -> Option<&[String]> {
+        self.operations.iter().find(|(name, _)| name == opcode).map(|(_, operands)| operands.as_slice())
+    }
+
+    /// Whether playing `san` passes this test position: it must be one of the `bm` moves if any are
+    /// given, and none of the `am` moves. Check and mate marks are ignored when comparing, and a move
+    /// that is not legal in the position is refused.
+    pub fn solves(&self, san: &str) -> Result<bool, String> {
+        let san = strip_marks(san.trim());
+        let legal = self.board.all_legal_moves(self.current_player).iter()
+            .any(|mv| generate_move_notation(&self.board, mv.from, mv.to, mv.promotion, false, false) == san);
+        if !legal {
+            return Err(format!("{} is not a legal move in this position", san));
+        }
+        let best = self.operation("bm").is_none_or(|moves| moves.iter().any(|best| strip_marks(best) == san));
+        let avoided = self.operation("am").is_some_and(|moves| moves.iter().any(|avoid| strip_marks(avoid) == san));
+        Ok(best && !avoided)
+    }
+}
+
+// Opcodes whose operands are always strings and so are always written quoted
+fn is_string_opcode(opcode: &str) -> bool {
+    matches!(opcode, "id" | "c0" | "c1" | "c2" | "c3" | "c4" | "c5" | "c6" | "c7" | "c8" | "c9")
+}
+
+fn strip_marks(san: &str) -> &str {
+    san.trim_end_matches(['+', '#', '!', '?'])
+}
+
+// Splits the operations on ';', except where the semicolon is inside a quoted string
+fn split_operations(text: &str) -> Result<Vec<String>, String> {
+    let mut operations = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    for c in text.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                current.push(c);
+            },
+            ';' if !quoted => operations.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+    if quoted {
+        return Err("An EPD string operand is missing its closing quote".to_string());
+    }
+    if !current.trim().is_empty() {
+        return Err(format!("The operation \"{}\" is missing its closing ';'", current.trim()));
+    }
+    Ok(operations)
+}
+
+// Breaks one operation into its opcode and operands, keeping quoted strings whole
+fn operands(operation: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut chars = operation.trim().chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '"' {
+            chars.next();
+            words.push(chars.by_ref().take_while(|&c| c != '"').collect());
+        } else {
+            let mut word = String::new();
+            while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                word.push(c);
+            }
+            words.push(word);
+        }
+    }
+    words
+}
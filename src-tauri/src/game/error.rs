@@ -0,0 +1,44 @@
+use std::fmt;
+
+use serde::Serialize;
+
+/// Why a move was refused, serialized by name so the frontend can pick its own wording.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MoveError {
+    NotYourTurn,
+    PieceMissing,
+    WouldLeaveKingInCheck,
+    DestinationBlocked,
+    UnreachableSquare,
+    GameAlreadyOver,
+    WrongPromotionRank,
+    InvalidPromotionPiece,
+    BoardBeingEdited,
+    GameBeingReviewed,
+    // The only refusal the Kriegspiel referee gives, whatever was wrong with the move
+    IllegalMove,
+    // Kriegspiel moves go through the referee, which does not answer with the whole board
+    PlayingKriegspiel,
+    StateUnavailable,
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            MoveError::NotYourTurn => "It is not your turn",
+            MoveError::PieceMissing => "There is no piece on the selected square",
+            MoveError::WouldLeaveKingInCheck => "That move would leave your king in check",
+            MoveError::DestinationBlocked => "The destination is occupied by one of your own pieces",
+            MoveError::UnreachableSquare => "That piece cannot move to the selected square",
+            MoveError::GameAlreadyOver => "The game is already over",
+            MoveError::WrongPromotionRank => "Only a pawn reaching the last rank can promote",
+            MoveError::InvalidPromotionPiece => "A pawn can only promote to a queen, rook, bishop or knight",
+            MoveError::BoardBeingEdited => "Finish editing the board before playing",
+            MoveError::GameBeingReviewed => "Return to the current position or analyse this one before playing",
+            MoveError::IllegalMove => "Illegal move",
+            MoveError::PlayingKriegspiel => "The board is hidden in Kriegspiel; play through the referee",
+            MoveError::StateUnavailable => "Failed to lock game state",
+        };
+        write!(f, "{}", message)
+    }
+}
@@ -0,0 +1,190 @@
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::cursor::BoardCursor;
+use super::epd::EpdRecord;
+use super::notation::parse_nag;
+use super::piece::Position;
+use super::scoresheet::import_scoresheet;
+use super::script::{parse_script, run_script};
+use super::setup::parse_setup;
+use super::state::GameState;
+
+/// One command as it was invoked from the frontend, with its arguments.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct JournalEntry {
+    // Milliseconds since the Unix epoch
+    pub time: i64,
+    pub command: String,
+    pub payload: Value,
+}
+
+/// Appends every invoked command to a JSON Lines file, so a session can be replayed when
+/// reproducing a bug report. Writing is best effort and never fails the command being recorded.
+pub struct Journal {
+    file: Option<File>,
+}
+
+impl Journal {
+    /// Starts a new journal at `path`, replacing the previous session's.
+    pub fn create(path: &Path) -> Journal {
+        let file = File::create(path)
+            .map_err(|error| eprintln!("Failed to create the journal {}: {}", path.display(), error))
+            .ok();
+        Journal { file }
+    }
+
+    pub fn record(&mut self, command: &str, payload: &Value) {
+        let Some(file) = self.file.as_mut() else {
+            return;
+        };
+        let entry = JournalEntry { time: chrono::Utc::now().timestamp_millis(), command: command.to_string(), payload: payload.clone() };
+        let written = serde_json::to_string(&entry)
+            .map_err(|error| error.to_string())
+            .and_then(|line| writeln!(file, "{}", line).map_err(|error| error.to_string()));
+        if let Err(error) = written {
+            eprintln!("Failed to record {} in the journal: {}", command, error);
+        }
+    }
+}
+
+/// A command that failed while the journal was replayed, by its 1-based line in the journal.
+#[derive(Serialize, Clone, Debug)]
+pub struct ReplayError {
+    pub line: usize,
+    pub command: String,
+    pub error: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ReplayReport {
+    // Commands that changed the game, whether they succeeded or not
+    pub replayed: usize,
+    // Commands that only read state or touch nothing the game depends on
+    pub skipped: usize,
+    // Failures are expected where the original command failed too, e.g. an illegal move
+    pub errors: Vec<ReplayError>,
+    // The command the replay stopped at because its outcome depends on files outside the journal
+    pub stopped_at: Option<ReplayError>,
+}
+
+/// Reads a journal written by `Journal` and plays its game commands again on a new game, in order.
+pub fn replay_journal(path: &Path) -> Result<(GameState, ReplayReport), String> {
+    let text = fs::read_to_string(path).map_err(|error| format!("Failed to read {}: {}", path.display(), error))?;
+    let mut game = GameState::new();
+    // Moves played with the selection cursor depend on where it was, so it is replayed too.
+    let mut cursor = BoardCursor::new();
+    let mut report = ReplayReport { replayed: 0, skipped: 0, errors: Vec::new(), stopped_at: None };
+
+    for (index, line) in text.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+        let entry: JournalEntry = serde_json::from_str(line)
+            .map_err(|error| format!("Line {} is not a journal entry: {}", index + 1, error))?;
+        let failure = |error: String| ReplayError { line: index + 1, command: entry.command.clone(), error };
+        match apply_command(&mut game, &mut cursor, &entry.command, &entry.payload) {
+            Ok(Applied::Replayed) => report.replayed += 1,
+            Ok(Applied::Skipped) => report.skipped += 1,
+            Ok(Applied::External) => {
+                report.stopped_at = Some(failure("Depends on files outside the journal".to_string()));
+                break;
+            },
+            Err(error) => {
+                report.replayed += 1;
+                report.errors.push(failure(error));
+            },
+        }
+    }
+    Ok((game, report))
+}
+
+/// What became of a command played through `apply_command`.
+pub enum Applied {
+    Replayed,
+    // The command does not change the game
+    Skipped,
+    // The command loads a game from a file or the database, which cannot be played without them
+    External,
+}
+
+/// Plays one command with its arguments on the game, doing to it what the command does to the
+/// live game.
+pub fn apply_command(game: &mut GameState, cursor: &mut BoardCursor, command: &str, args: &Value) -> Result<Applied, String> {
+    match command {
+        "move_piece" => {
+            game.move_piece_from(arg(args, "from")?, arg(args, "to")?, arg(args, "promotion")?)
+                .map_err(|error| error.to_string())?;
+        },
+        "cursor_move" => cursor.step(arg(args, "direction")?, game.turn_changed().orientation),
+        "cursor_select" => {
+            if let Some((from, to)) = cursor.select(game).map_err(|error| error.to_string())? {
+                game.move_piece_from(from, to, arg(args, "promotion")?).map_err(|error| error.to_string())?;
+            }
+        },
+        "undo_move" => game.undo_move()?,
+        "goto_move" => game.goto_move(arg(args, "index")?)?,
+        "enter_review" => {
+            game.review(arg(args, "index")?)?;
+        },
+        "leave_review" => game.leave_review(),
+        "set_kriegspiel" => game.set_kriegspiel(arg(args, "enabled")?),
+        "kriegspiel_move" => {
+            game.referee_move(arg(args, "from")?, arg(args, "to")?, arg(args, "promotion")?)
+                .map_err(|error| error.to_string())?;
+        },
+        "set_undo_policy" => game.set_undo_policy(arg(args, "policy")?),
+        "set_player_names" => game.set_player_names(arg(args, "players")?),
+        "set_game_metadata" => {
+            game.set_player_names(arg(args, "players")?);
+            game.set_metadata(arg(args, "metadata")?);
+        },
+        "resign" => game.resign(arg(args, "color")?)?,
+        "offer_draw" => game.offer_draw(arg(args, "color")?)?,
+        "respond_draw" => game.respond_draw(arg(args, "color")?, arg(args, "accept")?)?,
+        "claim_draw" => game.claim_draw(arg(args, "color")?)?,
+        "import_scoresheet" => *game = import_scoresheet(&arg::<String>(args, "text")?).0,
+        "setup_position" => {
+            let (board, to_move) = parse_setup(&arg::<String>(args, "description")?)?;
+            game.set_position(board, to_move);
+        },
+        "load_epd_position" => {
+            let record = EpdRecord::parse(&arg::<String>(args, "record")?)?;
+            game.set_position(record.board, record.current_player);
+        },
+        "start_editing" => game.start_editing(),
+        "clear_board" => game.clear_board()?,
+        "place_piece" => game.place_piece(Position::new(arg(args, "x")?, arg(args, "y")?), arg(args, "piece")?)?,
+        "remove_piece" => game.remove_piece(Position::new(arg(args, "x")?, arg(args, "y")?))?,
+        "set_side_to_move" => game.set_side_to_move(arg(args, "color")?)?,
+        "finish_editing" => game.finish_editing()?,
+        "annotate_move" => {
+            let nag: Option<String> = arg(args, "nag")?;
+            let nags = match nag.as_deref().map(str::trim).filter(|nag| !nag.is_empty()) {
+                Some(nag) => vec![parse_nag(nag).ok_or_else(|| format!("\"{}\" is not an annotation glyph", nag))?],
+                None => Vec::new(),
+            };
+            game.annotate_move(arg(args, "ply")?, arg(args, "text")?, nags)?;
+        },
+        "annotate_game" => game.annotate_game(arg(args, "text")?),
+        "run_script" => {
+            // Tauri passes the argument under its camelCase name
+            let steps = parse_script(&arg::<String>(args, "commandsJson")?)?;
+            let report = run_script(game, &steps, |_| {});
+            if let Some(error) = report.steps.last().and_then(|step| step.error.clone()) {
+                return Err(error);
+            }
+        },
+        "load_game" | "open_imported_game" | "load_stored_game" | "start_new_game_from_position" => return Ok(Applied::External),
+        _ => return Ok(Applied::Skipped),
+    }
+    Ok(Applied::Replayed)
+}
+
+// Reads a command argument, a missing one being read as null so optional arguments work
+fn arg<T: DeserializeOwned>(args: &Value, name: &str) -> Result<T, String> {
+    serde_json::from_value(args.get(name).cloned().unwrap_or(Value::Null))
+        .map_err(|error| format!("Bad \"{}\" argument: {}", name, error))
+}
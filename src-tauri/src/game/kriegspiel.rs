@@ -0,0 +1,107 @@
+use serde::Serialize;
+
+use super::board::{ChessBoard, BOARD_SIZE};
+use super::moves::MoveType;
+use super::piece::{Color, PieceType, Position};
+use super::state::GameState;
+
+/// What one player may see in Kriegspiel: their own pieces only, plus what the referee announced
+/// about the last move.
+#[derive(Serialize, Clone, Debug)]
+pub struct KriegspielView {
+    #[serde(flatten)]
+    pub board: ChessBoard,
+    pub current_player: Color,
+    pub announcements: Vec<String>,
+}
+
+/// Builds the view of the side to move, the only player at the board in pass-and-play, so the
+/// opponent's pieces cannot be asked for. The game itself holds the full position, so it acts as
+/// the referee: `GameState::referee_move` refuses illegal attempts with a bare "Illegal move".
+pub fn player_view(game: &GameState) -> KriegspielView {
+    let board = game.board();
+    let color = game.current_player();
+    let own_pieces = board.pieces().filter(|(_, piece)| piece.color == color);
+    KriegspielView {
+        board: ChessBoard::from_pieces(own_pieces),
+        current_player: game.current_player(),
+        announcements: announcements(game),
+    }
+}
+
+// The referee's announcements after the last move: the square of any capture, then the direction of
+// every check, then the end of the game, then any illegal attempt since
+fn announcements(game: &GameState) -> Vec<String> {
+    let mut announcements = Vec::new();
+    let last = game.move_history().last();
+    if let Some((last, captured)) = last.and_then(|last| Some((last, last.captured?))) {
+        let kind = if captured.is_pawn() { "Pawn" } else { "Piece" };
+        // En passant takes the pawn beside the destination, and that is the square announced.
+        let square = match last.played.move_type {
+            MoveType::EnPassant => Position::new(last.played.to.x, last.played.from.y),
+            _ => last.played.to,
+        };
+        announcements.push(format!("{} captured on {}", kind, square.to_algebraic()));
+    }
+
+    let defender = game.current_player();
+    if let Some(king) = game.board().find_king(defender) {
+        for checker in game.board().checkers(defender) {
+            announcements.push(format!("Check on the {}", check_direction(game.board(), king, checker)));
+        }
+    }
+
+    if let Some(result) = game.result() {
+        announcements.push(format!("Game over: {}", result.score()));
+    }
+    if game.illegal_attempt() {
+        announcements.push("Illegal move".to_string());
+    }
+    announcements
+}
+
+fn check_direction(board: &ChessBoard, king: Position, checker: Position) -> &'static str {
+    let (dx, dy) = (checker.x as i32 - king.x as i32, checker.y as i32 - king.y as i32);
+    if board.piece_at(checker).is_some_and(|piece| piece.piece_type == PieceType::Knight) {
+        return "knight";
+    }
+    if dy == 0 {
+        return "rank";
+    }
+    if dx == 0 {
+        return "file";
+    }
+
+    // Each square lies on two diagonals; the referee says whether the check comes along the longer one.
+    let size = BOARD_SIZE as i32;
+    let (x, y) = (king.x as i32, king.y as i32);
+    let falling = size - (x - y).abs();
+    let rising = size - (x + y - (size - 1)).abs();
+    let (along, other) = if dx == dy { (falling, rising) } else { (rising, falling) };
+    if along >= other { "long diagonal" } else { "short diagonal" }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::error::MoveError;
+
+    fn square(name: &str) -> Position {
+        Position::from_algebraic(name).unwrap()
+    }
+
+    #[test]
+    fn the_referee_refuses_without_saying_why() {
+        let mut game = GameState::new();
+        game.set_kriegspiel(true);
+        // A blocked square and an unreachable one get the same answer.
+        assert_eq!(game.referee_move(square("e1"), square("e2"), None).err(), Some(MoveError::IllegalMove));
+        assert_eq!(game.referee_move(square("e2"), square("e5"), None).err(), Some(MoveError::IllegalMove));
+        assert_eq!(player_view(&game).announcements, vec!["Illegal move"]);
+
+        game.referee_move(square("e2"), square("e4"), None).unwrap();
+        let view = player_view(&game);
+        assert!(view.announcements.is_empty());
+        assert!(view.board.pieces().all(|(_, piece)| piece.color == Color::Black));
+    }
+}
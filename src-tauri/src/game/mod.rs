@@ -2,3 +2,22 @@ pub mod state;
 pub mod piece;
 pub mod utils;
 pub mod board;
+pub mod movement;
+pub mod error;
+pub mod scoresheet;
+pub mod analysis;
+pub mod moves;
+pub mod notation;
+pub mod setup;
+pub mod pgn;
+pub mod saves;
+pub mod kriegspiel;
+pub mod database;
+pub mod codec;
+pub mod epd;
+pub mod eco;
+pub mod diagram;
+pub mod positions;
+pub mod journal;
+pub mod cursor;
+pub mod script;
@@ -0,0 +1,114 @@
+use super::board::ChessBoard;
+use super::piece::{Color, PieceType, Position};
+
+pub const KNIGHT_OFFSETS: [(i32, i32); 8] = [
+    (2, 1), (2, -1), (-2, 1), (-2, -1),
+    (1, 2), (1, -2), (-1, 2), (-1, -2),
+];
+pub const KING_OFFSETS: [(i32, i32); 8] = [
+    (1, 1), (1, 0), (1, -1), (0, 1), (0, -1), (-1, 1), (-1, 0), (-1, -1),
+];
+pub const STRAIGHT_DIRECTIONS: [(i32, i32); 4] = [(0, 1), (1, 0), (0, -1), (-1, 0)];
+pub const DIAGONAL_DIRECTIONS: [(i32, i32); 4] = [(1, 1), (1, -1), (-1, -1), (-1, 1)];
+
+/// Describes one way a piece travels across the board. A piece type is a list of these, so new
+/// pieces can be added by combining descriptors instead of writing bespoke move generation.
+#[derive(Clone, Copy, Debug)]
+pub enum Movement {
+    /// Jumps straight to each offset, ignoring anything in between (knight, king).
+    Leaper(&'static [(i32, i32)]),
+    /// Slides along each direction until it reaches the edge or another piece (rook, bishop).
+    Rider(&'static [(i32, i32)]),
+    /// Slides along each direction, jumps the first piece it meets and lands right behind it
+    /// (the grasshopper).
+    Hopper(&'static [(i32, i32)]),
+}
+
+impl Movement {
+    // Adds every destination reachable with this movement, capturing enemy pieces but never own ones
+    pub fn add_moves(&self, position: Position, board: &ChessBoard, color: Color, positions: &mut Vec<Position>) {
+        self.for_each_target(position, board, |target| {
+            if !matches!(board.piece_at(target), Some(piece) if piece.color == color) {
+                positions.push(target);
+            }
+        });
+    }
+
+    // Marks every square this movement attacks, including those holding pieces of the mover's own color
+    pub fn add_attacks(&self, position: Position, board: &ChessBoard, attacks: &mut u64) {
+        self.for_each_target(position, board, |target| *attacks |= target.bit());
+    }
+
+    fn for_each_target(&self, position: Position, board: &ChessBoard, mut visit: impl FnMut(Position)) {
+        match *self {
+            Movement::Leaper(offsets) => {
+                for &(dx, dy) in offsets {
+                    if let Some(target) = position.offset(dx, dy) {
+                        visit(target);
+                    }
+                }
+            },
+            Movement::Rider(directions) => {
+                for &(dx, dy) in directions {
+                    let mut current = position;
+                    while let Some(target) = current.offset(dx, dy) {
+                        visit(target);
+                        if board.piece_at(target).is_some() {
+                            break;
+                        }
+                        current = target;
+                    }
+                }
+            },
+            Movement::Hopper(directions) => {
+                for &(dx, dy) in directions {
+                    if let Some(screen) = first_piece_along(board, position, dx, dy) {
+                        if let Some(target) = screen.offset(dx, dy) {
+                            visit(target);
+                        }
+                    }
+                }
+            },
+        }
+    }
+
+    /// Returns true if this movement slides any distance along (dx, dy), as needed to pin a piece.
+    pub fn slides_along(&self, dx: i32, dy: i32) -> bool {
+        matches!(*self, Movement::Rider(directions) if directions.contains(&(dx, dy)))
+    }
+
+    /// Returns true if a `piece_type` of color `by` using this movement attacks `target`.
+    pub fn attacks(&self, target: Position, board: &ChessBoard, piece_type: PieceType, by: Color) -> bool {
+        let is_attacker = |position: Position| {
+            board.piece_at(position).is_some_and(|piece| piece.piece_type == piece_type && piece.color == by)
+        };
+
+        // Walk backwards from the target to find where such an attacker would have to stand.
+        match *self {
+            Movement::Leaper(offsets) => offsets.iter().any(|&(dx, dy)| {
+                target.offset(-dx, -dy).is_some_and(is_attacker)
+            }),
+            Movement::Rider(directions) => directions.iter().any(|&(dx, dy)| {
+                first_piece_along(board, target, -dx, -dy).is_some_and(is_attacker)
+            }),
+            Movement::Hopper(directions) => directions.iter().any(|&(dx, dy)| {
+                target.offset(-dx, -dy)
+                    .filter(|&screen| board.piece_at(screen).is_some())
+                    .and_then(|screen| first_piece_along(board, screen, -dx, -dy))
+                    .is_some_and(is_attacker)
+            }),
+        }
+    }
+}
+
+// Returns the position of the first piece met when walking from `position` (exclusive) along (dx, dy)
+fn first_piece_along(board: &ChessBoard, position: Position, dx: i32, dy: i32) -> Option<Position> {
+    let mut current = position;
+    while let Some(next) = current.offset(dx, dy) {
+        if board.piece_at(next).is_some() {
+            return Some(next);
+        }
+        current = next;
+    }
+    None
+}
@@ -0,0 +1,66 @@
+use serde::{Deserialize, Serialize};
+
+use super::piece::{PieceType, Position};
+
+/// What kind of move was played, so special moves survive serialization without being re-derived.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MoveType {
+    Normal,
+    Capture,
+    DoublePawnPush,
+    EnPassant,
+    KingsideCastle,
+    QueensideCastle,
+}
+
+/// A single move. `promotion` is the piece a pawn turns into on the last rank, on top of whichever
+/// `move_type` got it there.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Move {
+    pub from: Position,
+    pub to: Position,
+    pub promotion: Option<PieceType>,
+    pub move_type: MoveType,
+}
+
+impl Move {
+    pub fn is_capture(&self) -> bool {
+        matches!(self.move_type, MoveType::Capture | MoveType::EnPassant)
+    }
+}
+
+/// Everything worth knowing about a move once it has been played.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct MoveRecord {
+    #[serde(flatten)]
+    pub played: Move,
+    pub san: String,
+    pub piece: PieceType,
+    pub captured: Option<PieceType>,
+    pub check: bool,
+    pub checkmate: bool,
+    // Hash of the position the move led to (see `ChessBoard::position_hash`), sent as 16 hex
+    // digits since JavaScript numbers cannot hold every 64-bit value
+    #[serde(with = "hex_hash")]
+    pub position_hash: u64,
+    // Milliseconds since the Unix epoch
+    pub timestamp: i64,
+    #[serde(default)]
+    pub comment: Option<String>,
+    // Numeric Annotation Glyphs as in PGN, e.g. 1 for "!" and 6 for "?!"
+    #[serde(default)]
+    pub nags: Vec<u8>,
+}
+
+mod hex_hash {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(hash: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format!("{:016x}", hash))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        let hex = String::deserialize(deserializer)?;
+        u64::from_str_radix(&hex, 16).map_err(serde::de::Error::custom)
+    }
+}
@@ -0,0 +1,159 @@
+use serde::{Deserialize, Serialize};
+
+use super::piece::{Color, PieceType};
+
+/// The ways a move from the history can be written out.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NotationStyle {
+    San,
+    Figurine,
+    German,
+    French,
+    Spanish,
+    // Whole words for screen readers, e.g. "Knight takes f3, check"
+    Spoken,
+}
+
+/// Writes a SAN entry of the move history in `style`. `ply` is its 0-based index in the history, which
+/// tells whose move it was. Score entries such as "1-0" are only reworded by the spoken style.
+pub fn render(san: &str, ply: usize, style: NotationStyle) -> String {
+    let mover = [Color::White, Color::Black][ply % 2];
+    if is_score(san) {
+        return match style {
+            NotationStyle::Spoken => spoken_score(san),
+            _ => san.to_string(),
+        };
+    }
+
+    match style {
+        NotationStyle::San => san.to_string(),
+        NotationStyle::Figurine => replace_piece_letters(san, |piece_type| figurine(piece_type, mover)),
+        NotationStyle::German => replace_piece_letters(san, |piece_type| localized_letter(piece_type, "KDTLSB")),
+        NotationStyle::French => replace_piece_letters(san, |piece_type| localized_letter(piece_type, "RDTFCP")),
+        NotationStyle::Spanish => replace_piece_letters(san, |piece_type| localized_letter(piece_type, "RDTACP")),
+        NotationStyle::Spoken => spoken(san),
+    }
+}
+
+/// Reads a Numeric Annotation Glyph written either as "$n" or as one of the move symbols "!", "?",
+/// "!!", "??", "!?" and "?!".
+pub fn parse_nag(text: &str) -> Option<u8> {
+    match text {
+        "!" => Some(1),
+        "?" => Some(2),
+        "!!" => Some(3),
+        "??" => Some(4),
+        "!?" => Some(5),
+        "?!" => Some(6),
+        _ => text.strip_prefix('$')?.parse().ok(),
+    }
+}
+
+fn is_score(san: &str) -> bool {
+    matches!(san, "1-0" | "0-1" | "1/2-1/2" | "*")
+}
+
+// Piece letters are the only uppercase characters in SAN apart from the O of castling
+fn replace_piece_letters(san: &str, replacement: impl Fn(PieceType) -> char) -> String {
+    san.chars()
+        .map(|c| match PieceType::from_symbol(c) {
+            Some(piece_type) => replacement(piece_type),
+            None => c,
+        })
+        .collect()
+}
+
+pub fn figurine(piece_type: PieceType, color: Color) -> char {
+    let (white, black) = match piece_type {
+        PieceType::King => ('♔', '♚'),
+        PieceType::Queen => ('♕', '♛'),
+        PieceType::Rook => ('♖', '♜'),
+        PieceType::Bishop => ('♗', '♝'),
+        PieceType::Knight => ('♘', '♞'),
+        PieceType::Pawn => ('♙', '♟'),
+    };
+    if color == Color::White { white } else { black }
+}
+
+// `letters` lists the local letters in the order king, queen, rook, bishop, knight, pawn
+fn localized_letter(piece_type: PieceType, letters: &str) -> char {
+    let index = match piece_type {
+        PieceType::King => 0,
+        PieceType::Queen => 1,
+        PieceType::Rook => 2,
+        PieceType::Bishop => 3,
+        PieceType::Knight => 4,
+        PieceType::Pawn => 5,
+    };
+    letters.chars().nth(index).unwrap_or(piece_type.symbol())
+}
+
+fn piece_name(piece_type: PieceType) -> &'static str {
+    match piece_type {
+        PieceType::King => "king",
+        PieceType::Queen => "queen",
+        PieceType::Rook => "rook",
+        PieceType::Bishop => "bishop",
+        PieceType::Knight => "knight",
+        PieceType::Pawn => "pawn",
+    }
+}
+
+fn spoken(san: &str) -> String {
+    let suffix = if san.ends_with('#') {
+        ", checkmate"
+    } else if san.ends_with('+') {
+        ", check"
+    } else {
+        ""
+    };
+    let body = san.trim_end_matches(['+', '#']);
+
+    let mut words = match body {
+        "O-O" => "castles kingside".to_string(),
+        "O-O-O" => "castles queenside".to_string(),
+        _ => {
+            let (body, promotion) = match body.split_once('=') {
+                Some((body, promoted)) => (body, promoted.chars().next().and_then(PieceType::from_symbol)),
+                None => (body, None),
+            };
+            let piece_type = body.chars().next().and_then(PieceType::from_symbol);
+            let body = if piece_type.is_some() { &body[1..] } else { body };
+            let (hint, destination) = body.split_at(body.len().saturating_sub(2));
+            let (hint, captures) = match hint.strip_suffix('x') {
+                Some(hint) => (hint, true),
+                None => (hint, false),
+            };
+
+            let mut words = piece_name(piece_type.unwrap_or(PieceType::Pawn)).to_string();
+            if !hint.is_empty() {
+                words.push_str(&format!(" on {}", hint));
+            }
+            words.push_str(if captures { " takes " } else { " to " });
+            words.push_str(destination);
+            if let Some(promotion) = promotion {
+                words.push_str(&format!(", promotes to {}", piece_name(promotion)));
+            }
+            words
+        },
+    };
+    words.push_str(suffix);
+    capitalize(&words)
+}
+
+fn spoken_score(score: &str) -> String {
+    match score {
+        "1-0" => "White wins",
+        "0-1" => "Black wins",
+        "1/2-1/2" => "Draw",
+        _ => "Game in progress",
+    }.to_string()
+}
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
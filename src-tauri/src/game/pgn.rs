@@ -0,0 +1,366 @@
+use serde::Serialize;
+
+use super::board::ChessBoard;
+use super::notation::parse_nag;
+use super::piece::Color;
+use super::scoresheet::{import_scoresheet_from, tokenize};
+use super::state::{GameMetadata, GameResult, GameState, PlayerNames};
+
+const LINE_WIDTH: usize = 80;
+
+/// Writes the game as PGN: the Seven Tag Roster, SetUp and FEN tags when it began from a set-up
+/// position, then the SAN movetext closed by the result. The date is the one in the game's metadata.
+pub fn to_pgn(game: &GameState) -> String {
+    let result = game.result().map_or("*", |result| result.score());
+    let or = |value: &str, unknown: &str| if value.trim().is_empty() { unknown.to_string() } else { value.to_string() };
+    let metadata = game.metadata();
+
+    let mut tags = vec![
+        ("Event", or(&metadata.event, "Casual game")),
+        ("Site", or(&metadata.site, "?")),
+        ("Date", or(&metadata.date, "????.??.??")),
+        ("Round", or(&metadata.round, "-")),
+        ("White", or(&game.players().white, "?")),
+        ("Black", or(&game.players().black, "?")),
+        ("Result", result.to_string()),
+    ];
+    let start = game.start_position();
+    if let Some((board, to_move)) = start {
+        tags.push(("SetUp", "1".to_string()));
+        tags.push(("FEN", board.to_fen(to_move, 0, 1)));
+    }
+
+    let mut pgn: String = tags.iter().map(|(tag, value)| format!("[{} \"{}\"]\n", tag, escape(value))).collect();
+    pgn.push('\n');
+
+    // Count plies from white's first move, so a game where black moves first starts at ply 1.
+    let first_ply = usize::from(start.is_some_and(|(_, to_move)| to_move == Color::Black));
+    let mut tokens = game.comment().map(comment_tokens).unwrap_or_default();
+    // Black's move needs its number repeated when something came between it and white's move.
+    let mut interrupted = true;
+    for (index, record) in game.move_history().iter().enumerate() {
+        let ply = index + first_ply;
+        match (ply % 2, interrupted) {
+            (0, _) => tokens.push(format!("{}.", ply / 2 + 1)),
+            (_, true) => tokens.push(format!("{}...", ply / 2 + 1)),
+            _ => {},
+        }
+        tokens.push(record.san.clone());
+        tokens.extend(record.nags.iter().map(|nag| format!("${}", nag)));
+        interrupted = record.comment.is_some();
+        if let Some(comment) = &record.comment {
+            tokens.extend(comment_tokens(comment));
+        }
+    }
+    tokens.push(result.to_string());
+
+    pgn.push_str(&wrap(&tokens));
+    pgn.push('\n');
+    pgn
+}
+
+// Writes a comment as brace-delimited words, so a long comment wraps like the moves around it
+fn comment_tokens(comment: &str) -> Vec<String> {
+    let words: Vec<&str> = comment.split(['{', '}']).flat_map(str::split_whitespace).collect();
+    format!("{{{}}}", words.join(" ")).split(' ').map(str::to_string).collect()
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+// Joins the movetext tokens with spaces, breaking lines before they pass the PGN line width
+fn wrap(tokens: &[String]) -> String {
+    let mut text = String::new();
+    let mut line_length = 0;
+    for token in tokens {
+        if line_length > 0 && line_length + 1 + token.len() > LINE_WIDTH {
+            text.push('\n');
+            line_length = 0;
+        } else if line_length > 0 {
+            text.push(' ');
+            line_length += 1;
+        }
+        text.push_str(token);
+        line_length += token.len();
+    }
+    text
+}
+
+/// One game cut out of a PGN file, with its tags parsed and its movetext left as written.
+#[derive(Clone, Debug, Default)]
+pub struct PgnGame {
+    pub tags: Vec<(String, String)>,
+    pub movetext: String,
+}
+
+/// What the import dialog lists for each game before anything is imported.
+#[derive(Serialize, Clone, Debug)]
+pub struct PgnPreview {
+    pub index: usize,
+    pub event: String,
+    pub date: String,
+    pub white: String,
+    pub black: String,
+    pub result: String,
+    pub move_count: usize,
+}
+
+/// Games read from a PGN file and waiting to be picked, and those imported from it so far.
+#[derive(Default)]
+pub struct PgnLibrary {
+    pub pending: Vec<PgnGame>,
+    pub imported: Vec<(PgnPreview, GameState)>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ImportProgress {
+    pub done: usize,
+    pub total: usize,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ImportFinished {
+    pub imported: usize,
+    // Index of each game that could not be imported, with the reason
+    pub failures: Vec<(usize, String)>,
+}
+
+/// Cuts a PGN file into its games. A tag line after some movetext starts the next game.
+pub fn split_games(text: &str) -> Vec<PgnGame> {
+    let mut games = Vec::new();
+    let mut current = PgnGame::default();
+    for line in text.lines().map(str::trim) {
+        if line.starts_with('[') && line.ends_with(']') {
+            if !current.movetext.trim().is_empty() {
+                games.push(std::mem::take(&mut current));
+            }
+            if let Some(tag) = parse_tag(line) {
+                current.tags.push(tag);
+            }
+        } else if !line.starts_with('%') {
+            current.movetext.push_str(line);
+            current.movetext.push('\n');
+        }
+    }
+    if !current.tags.is_empty() || !current.movetext.trim().is_empty() {
+        games.push(current);
+    }
+    games
+}
+
+// Reads `[Name "Value"]`, undoing the escapes to_pgn writes
+fn parse_tag(line: &str) -> Option<(String, String)> {
+    let (name, value) = line[1..line.len() - 1].trim().split_once(char::is_whitespace)?;
+    let value = value.trim().strip_prefix('"')?.strip_suffix('"')?;
+    Some((name.to_string(), value.replace("\\\"", "\"").replace("\\\\", "\\")))
+}
+
+impl PgnGame {
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags.iter().find(|(tag, _)| tag == name).map(|(_, value)| value.as_str())
+    }
+
+    pub fn preview(&self, index: usize) -> PgnPreview {
+        let tag = |name: &str| self.tag(name).unwrap_or("?").to_string();
+        PgnPreview {
+            index,
+            event: tag("Event"),
+            date: tag("Date"),
+            white: tag("White"),
+            black: tag("Black"),
+            result: self.tag("Result").unwrap_or("*").to_string(),
+            move_count: tokenize(&self.movetext).len(),
+        }
+    }
+
+    /// Replays the game, from the position in its FEN tag if it has one. Its headers supply the
+    /// players, the event, site, date and round, and the result when the game did not end on the
+    /// board.
+    pub fn to_game_state(&self) -> Result<GameState, String> {
+        let start = match self.tag("FEN") {
+            Some(fen) => {
+                let (board, to_move) = ChessBoard::from_fen(fen)?;
+                GameState::from_position(board, to_move)
+            },
+            None if self.tag("SetUp") == Some("1") => return Err("The game is set up but has no FEN tag".to_string()),
+            None => GameState::new(),
+        };
+
+        let (mut game, report) = import_scoresheet_from(start, &self.movetext);
+        if let Some(token) = report.unparsed.first() {
+            let problem = report.issues.last().map_or("the game was already over", |issue| issue.problem.as_str());
+            return Err(format!("Stopped at \"{}\": {}", token, problem));
+        }
+
+        let annotations = read_annotations(&self.movetext);
+        game.annotate_game(annotations.game);
+        let plies = game.move_history().len();
+        for (index, (comment, nags)) in annotations.moves.into_iter().enumerate().take(plies) {
+            game.annotate_move(index + 1, comment, nags)?;
+        }
+
+        // "?" and "-" stand for an unknown value
+        let known = |name: &str| self.tag(name).filter(|&value| !matches!(value, "?" | "-")).unwrap_or_default().to_string();
+        game.set_player_names(PlayerNames { white: known("White"), black: known("Black") });
+        // A date of question marks only is unknown, while a partly known one is kept
+        let date = self.tag("Date").filter(|date| date.contains(|c: char| c.is_ascii_digit())).unwrap_or_default().to_string();
+        game.set_metadata(GameMetadata { event: known("Event"), site: known("Site"), date, round: known("Round") });
+        if !game.is_game_over() {
+            // The header gives the score but not how the game ended
+            let result = match self.tag("Result") {
+                Some("1-0") => Some(GameResult::Adjudicated { winner: Some(Color::White) }),
+                Some("0-1") => Some(GameResult::Adjudicated { winner: Some(Color::Black) }),
+                Some("1/2-1/2") => Some(GameResult::Adjudicated { winner: None }),
+                _ => None,
+            };
+            if let Some(result) = result {
+                game.adjudicate(result)?;
+            }
+        }
+        Ok(game)
+    }
+}
+
+// Comments and NAGs in a game's movetext: the comment before the first move, then the comment and
+// NAGs following each move
+struct Annotations {
+    game: Option<String>,
+    moves: Vec<(Option<String>, Vec<u8>)>,
+}
+
+impl Annotations {
+    fn add_comment(&mut self, text: &str) {
+        if text.trim().is_empty() {
+            return;
+        }
+        let target = match self.moves.last_mut() {
+            Some((comment, _)) => comment,
+            None => &mut self.game,
+        };
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        *target = match target.take() {
+            Some(earlier) => Some(format!("{} {}", earlier, text)),
+            None => Some(text),
+        };
+    }
+
+    // A movetext word is a NAG, a move possibly followed by "!" or "?" marks, or something to skip
+    fn add_word(&mut self, word: &str) {
+        if let Some(nag) = parse_nag(word) {
+            if let Some((_, nags)) = self.moves.last_mut() {
+                nags.push(nag);
+            }
+        } else if !tokenize(word).is_empty() {
+            let marks = &word[word.trim_end_matches(['!', '?']).len()..];
+            self.moves.push((None, parse_nag(marks).into_iter().collect()));
+        }
+    }
+}
+
+// Reads the annotations, counting moves the same way tokenize does and skipping variations
+fn read_annotations(movetext: &str) -> Annotations {
+    let mut annotations = Annotations { game: None, moves: Vec::new() };
+    let mut word = String::new();
+    let mut comment: Option<String> = None;
+    let mut in_line_comment = false;
+    let mut depth = 0usize;
+
+    for c in movetext.chars() {
+        if let Some(text) = comment.as_mut() {
+            match c {
+                '}' if !in_line_comment => annotations.add_comment(&comment.take().unwrap_or_default()),
+                '\n' if in_line_comment => {
+                    in_line_comment = false;
+                    annotations.add_comment(&comment.take().unwrap_or_default());
+                },
+                _ => text.push(c),
+            }
+            continue;
+        }
+        if depth > 0 {
+            match c {
+                '(' | '{' => depth += 1,
+                ')' | '}' => depth -= 1,
+                _ => {},
+            }
+            continue;
+        }
+
+        if matches!(c, '{' | '(' | ';') || c.is_whitespace() {
+            annotations.add_word(&std::mem::take(&mut word));
+        }
+        match c {
+            '{' => comment = Some(String::new()),
+            ';' => {
+                comment = Some(String::new());
+                in_line_comment = true;
+            },
+            '(' => depth = 1,
+            _ if c.is_whitespace() => {},
+            _ => word.push(c),
+        }
+    }
+    annotations.add_word(&word);
+    if let Some(text) = comment {
+        annotations.add_comment(&text);
+    }
+    annotations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::piece::Position;
+    use crate::game::scoresheet::import_scoresheet;
+
+    fn square(name: &str) -> Position {
+        Position::from_algebraic(name).unwrap()
+    }
+
+    fn reimport(game: &GameState) -> GameState {
+        let games = split_games(&to_pgn(game));
+        assert_eq!(games.len(), 1);
+        games[0].to_game_state().unwrap()
+    }
+
+    #[test]
+    fn exported_games_import_unchanged() {
+        let (mut game, _) = import_scoresheet("e4 e5 Nf3 Nc6 Bb5 a6 Ba4 Nf6 O-O Be7 Re1 b5 Bb3 d6 c3 O-O h3");
+        game.set_player_names(PlayerNames { white: "Alice".to_string(), black: "Bob".to_string() });
+        game.set_metadata(GameMetadata { event: "Club".to_string(), site: String::new(), date: "2024.05.18".to_string(), round: "3".to_string() });
+        game.annotate_game(Some("A quiet Spanish".to_string()));
+        game.annotate_move(5, Some("The Ruy Lopez".to_string()), vec![1]).unwrap();
+        game.resign(Color::Black).unwrap();
+
+        let imported = reimport(&game);
+        assert_eq!(imported.san_history(), game.san_history());
+        assert_eq!(imported.to_fen(), game.to_fen());
+        assert_eq!(imported.result(), Some(GameResult::Adjudicated { winner: Some(Color::White) }));
+        assert_eq!(imported.players().white, "Alice");
+        assert_eq!(imported.metadata().event, "Club");
+        assert_eq!(imported.metadata().date, "2024.05.18");
+        assert_eq!(imported.metadata().round, "3");
+        assert_eq!(imported.comment(), Some("A quiet Spanish"));
+        assert_eq!(imported.move_history()[4].comment.as_deref(), Some("The Ruy Lopez"));
+        assert_eq!(imported.move_history()[4].nags, vec![1]);
+    }
+
+    #[test]
+    fn games_from_a_position_import_from_their_fen() {
+        let (board, to_move) = ChessBoard::from_fen("4k3/8/8/8/8/8/4P3/4K3 b - - 0 1").unwrap();
+        let mut game = GameState::from_position(board, to_move);
+        game.move_piece_from(square("e8"), square("d7"), None).unwrap();
+        game.move_piece_from(square("e2"), square("e4"), None).unwrap();
+
+        let imported = reimport(&game);
+        assert_eq!(imported.move_history().len(), 2);
+        assert_eq!(imported.to_fen(), game.to_fen());
+    }
+
+    #[test]
+    fn set_up_games_need_a_fen() {
+        let games = split_games("[SetUp \"1\"]\n\n1. e4 *\n");
+        assert!(games[0].to_game_state().is_err());
+    }
+}
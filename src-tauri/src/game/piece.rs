@@ -1,14 +1,62 @@
 use serde::{Serialize, Deserialize};
 
-use super::board::BOARD_SIZE;
+use super::board::{ChessBoard, BOARD_SIZE};
+use super::movement::{Movement, DIAGONAL_DIRECTIONS, KING_OFFSETS, KNIGHT_OFFSETS, STRAIGHT_DIRECTIONS};
 
 
-#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
 pub enum PieceType {
     Pawn, Rook, Knight, Bishop, Queen, King,
 }
 
 impl PieceType {
+    pub const ALL: [PieceType; 6] = [
+        PieceType::Pawn, PieceType::Rook, PieceType::Knight, PieceType::Bishop, PieceType::Queen, PieceType::King,
+    ];
+
+    // How the piece moves and captures. Pawn pushes and castling depend on more than the
+    // geometry and are generated separately.
+    pub fn movement(&self) -> &'static [Movement] {
+        match self {
+            PieceType::Pawn => &[],
+            PieceType::Rook => &[Movement::Rider(&STRAIGHT_DIRECTIONS)],
+            PieceType::Knight => &[Movement::Leaper(&KNIGHT_OFFSETS)],
+            PieceType::Bishop => &[Movement::Rider(&DIAGONAL_DIRECTIONS)],
+            PieceType::Queen => &[Movement::Rider(&STRAIGHT_DIRECTIONS), Movement::Rider(&DIAGONAL_DIRECTIONS)],
+            PieceType::King => &[Movement::Leaper(&KING_OFFSETS)],
+        }
+    }
+
+    // Pieces a pawn may turn into, strongest first
+    pub const PROMOTIONS: [PieceType; 4] = [PieceType::Queen, PieceType::Rook, PieceType::Bishop, PieceType::Knight];
+
+    pub fn from_symbol(symbol: char) -> Option<PieceType> {
+        PieceType::ALL.iter().copied().find(|piece_type| piece_type.symbol() == symbol)
+    }
+
+    // Letter used for the piece in move notation
+    pub fn symbol(&self) -> char {
+        match self {
+            PieceType::Pawn => 'P',
+            PieceType::Rook => 'R',
+            PieceType::Knight => 'N',
+            PieceType::Bishop => 'B',
+            PieceType::Queen => 'Q',
+            PieceType::King => 'K',
+        }
+    }
+
+    // Conventional material value in pawns; the king is never traded so it counts for nothing
+    pub fn value(&self) -> u32 {
+        match self {
+            PieceType::Pawn => 1,
+            PieceType::Knight | PieceType::Bishop => 3,
+            PieceType::Rook => 5,
+            PieceType::Queen => 9,
+            PieceType::King => 0,
+        }
+    }
+
      pub fn is_pawn(&self) -> bool {
         match self {
             PieceType::Pawn => true,
@@ -29,6 +77,22 @@ impl Color {
             Color::Black => Color::White,
         }
     }
+
+    // White pawns advance towards row 0, black pawns towards the last row
+    pub fn pawn_direction(&self) -> i32 {
+        match self {
+            Color::White => -1,
+            Color::Black => 1,
+        }
+    }
+
+    // The row holding this side's king and rooks at the start of the game
+    pub fn back_rank(&self) -> usize {
+        match self {
+            Color::White => BOARD_SIZE - 1,
+            Color::Black => 0,
+        }
+    }
 }
 
 impl PartialEq for Color {
@@ -41,7 +105,9 @@ impl PartialEq for Color {
     }
 }
 
-#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+impl Eq for Color {}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
 pub struct Position {
     pub x: usize,
     pub y: usize,
@@ -51,6 +117,44 @@ impl Position {
     pub fn new(x: usize, y: usize) -> Self {
         Position { x, y }
     }
+
+    // Parses a square name such as "e4"
+    pub fn from_algebraic(square: &str) -> Option<Position> {
+        let mut chars = square.chars();
+        let (file, rank) = (chars.next()?, chars.next()?.to_digit(10)? as usize);
+        if chars.next().is_some() || !('a'..='h').contains(&file) || !(1..=BOARD_SIZE).contains(&rank) {
+            return None;
+        }
+        Some(Position::new(file as usize - 'a' as usize, BOARD_SIZE - rank))
+    }
+
+    // Square name in algebraic notation, e.g. "e4"
+    pub fn to_algebraic(self) -> String {
+        format!("{}{}", self.file(), self.rank())
+    }
+
+    pub fn file(&self) -> char {
+        (b'a' + self.x as u8) as char
+    }
+
+    pub fn rank(&self) -> usize {
+        BOARD_SIZE - self.y
+    }
+
+    // The bit standing for this square in a u64 square set such as an attack map
+    pub fn bit(&self) -> u64 {
+        1 << (self.y * BOARD_SIZE + self.x)
+    }
+
+    // Returns the position shifted by (dx, dy), or None if that falls off the board
+    pub fn offset(&self, dx: i32, dy: i32) -> Option<Position> {
+        let x = self.x as i32 + dx;
+        let y = self.y as i32 + dy;
+        if x < 0 || y < 0 || x >= BOARD_SIZE as i32 || y >= BOARD_SIZE as i32 {
+            return None;
+        }
+        Some(Position::new(x as usize, y as usize))
+    }
 }
 
 
@@ -58,11 +162,14 @@ impl Position {
 pub struct Piece {
     pub piece_type: PieceType,
     pub color: Color,
+    // Needed for castling; boards sent from the frontend may omit it
+    #[serde(default)]
+    pub has_moved: bool,
 }
 
 impl Piece {
     pub fn new(piece_type: PieceType, color: Color) -> Self {
-        Piece { piece_type, color }
+        Piece { piece_type, color, has_moved: false }
     }
 }
 
@@ -78,90 +185,89 @@ impl Square {
     pub fn new(x: usize, y: usize, piece: Option<Piece>) -> Self {
         Square { x, y, piece }
     }
+
+    pub fn position(&self) -> Position {
+        Position::new(self.x, self.y)
+    }
+
+    pub fn piece(&self) -> Option<Piece> {
+        self.piece
+    }
+
+    pub fn set_piece(&mut self, piece: Option<Piece>) {
+        self.piece = piece;
+    }
 }
 
 impl Square {
     // Calculates the valid moves for the piece in this square
-    pub fn calculate_moves(&self, position: Position, board: &Vec<Vec<Option<Square>>>) -> Vec<Position> {
+    pub fn calculate_moves(&self, position: Position, board: &ChessBoard) -> Vec<Position> {
         let mut positions = Vec::new();
         if let Some(piece) = &self.piece {
+            for movement in piece.piece_type.movement() {
+                movement.add_moves(position, board, piece.color, &mut positions);
+            }
             match piece.piece_type {
-                PieceType::Knight => {
-                    let knight_moves = [
-                        (2, 1), (2, -1), (-2, 1), (-2, -1),
-                        (1, 2), (1, -2), (-1, 2), (-1, -2),
-                    ];
-                    self.add_moves(&knight_moves, position, board, piece.color, &mut positions, false);
-                },
-                PieceType::Pawn => {
-                    let direction = if piece.color == Color::White { -1 } else { 1 };
-                    let start_row = if piece.color == Color::White { 6 } else { 1 };
-                    let single_step = (0, direction);
-                    let double_step = (0, 2 * direction);
-                    let captures = [(-1, direction), (1, direction)];
-
-                    self.add_moves(&[single_step], position, board, piece.color, &mut positions, true);
-                    if position.y == start_row {
-                        self.add_moves(&[double_step], position, board, piece.color, &mut positions, true);
-                    }
-                    self.add_moves(&captures, position, board, piece.color, &mut positions, false);
-                },
-                PieceType::Rook => {
-                    self.add_line_moves(&[(0, 1), (1, 0), (0, -1), (-1, 0)], position, board, piece.color, &mut positions);
-                },
-                PieceType::Bishop => {
-                    self.add_line_moves(&[(1, 1), (1, -1), (-1, -1), (-1, 1)], position, board, piece.color, &mut positions);
-                },
-                PieceType::Queen => {
-                    self.add_line_moves(&[(1, 1), (1, 0), (1, -1), (0, 1), (0, -1), (-1, 1), (-1, 0), (-1, -1)], position, board, piece.color, &mut positions);
-                },
-                PieceType::King => {
-                    let king_moves = [
-                        (1, 1), (1, 0), (1, -1), (0, 1), (0, -1), (-1, 1), (-1, 0), (-1, -1),
-                    ];
-                    self.add_moves(&king_moves, position, board, piece.color, &mut positions, false);
-                },
-                _ => {}
+                PieceType::Pawn => self.add_pawn_moves(position, board, piece.color, &mut positions),
+                PieceType::King => self.add_castling_moves(position, board, *piece, &mut positions),
+                _ => {},
             }
         }
 
         positions
     }
 
-    fn add_moves(&self, moves: &[(i32, i32)], position: Position, board: &Vec<Vec<Option<Square>>>, color: Color, positions: &mut Vec<Position>, only_empty: bool) {
-        for &(dx, dy) in moves {
-            let new_x = (position.x as i32 + dx) as usize;
-            let new_y = (position.y as i32 + dy) as usize;
-            if new_x < BOARD_SIZE && new_y < BOARD_SIZE {
-                match board.get(new_y).and_then(|row| row.get(new_x)) {
-                    Some(Some(target_square)) if only_empty || target_square.piece.as_ref().map_or(false, |p| p.color != color) => positions.push(Position::new(new_x, new_y)),
-                    Some(None) if only_empty => positions.push(Position::new(new_x, new_y)),
-                    _ => {}
+    fn add_pawn_moves(&self, position: Position, board: &ChessBoard, color: Color, positions: &mut Vec<Position>) {
+        let direction = color.pawn_direction();
+        let start_row = if color == Color::White { BOARD_SIZE - 2 } else { 1 };
+
+        if let Some(single_step) = position.offset(0, direction).filter(|&target| board.piece_at(target).is_none()) {
+            positions.push(single_step);
+            if position.y == start_row {
+                if let Some(double_step) = position.offset(0, 2 * direction).filter(|&target| board.piece_at(target).is_none()) {
+                    positions.push(double_step);
+                }
+            }
+        }
+
+        for dx in [-1, 1] {
+            if let Some(target) = position.offset(dx, direction) {
+                let is_capture = board.piece_at(target).is_some_and(|piece| piece.color != color);
+                if is_capture || board.is_en_passant(position, target) {
+                    positions.push(target);
                 }
             }
         }
     }
-    
-
-    fn add_line_moves(&self, directions: &[(i32, i32)], position: Position, board: &Vec<Vec<Option<Square>>>, color: Color, positions: &mut Vec<Position>) {
-        for &(dx, dy) in directions {
-            let mut step = 1;
-            loop {
-                let new_x = (position.x as i32 + dx * step).max(0) as usize;
-                let new_y = (position.y as i32 + dy * step).max(0) as usize;
-                if new_x >= BOARD_SIZE || new_y >= BOARD_SIZE { break; }
-                match &board[new_x][new_y] {
-                    Some(target_square) if target_square.piece.is_some() => {
-                        if target_square.piece.as_ref().unwrap().color != color {
-                            positions.push(Position::new(new_x, new_y));
-                        }
-                        break;
-                    },
-                    None => positions.push(Position::new(new_x, new_y)),
-                    _ => break,
+
+    // Castling: an unmoved king on its home square may move two files towards an unmoved rook,
+    // provided the squares between them are empty and the king neither starts in, passes through
+    // nor lands on an attacked square.
+    fn add_castling_moves(&self, position: Position, board: &ChessBoard, king: Piece, positions: &mut Vec<Position>) {
+        let home_row = king.color.back_rank();
+        if position != Position::new(4, home_row) {
+            return;
+        }
+        let attacked = board.attacked_squares(king.color.opposite());
+        if attacked & position.bit() != 0 {
+            return;
+        }
+
+        for (kingside, rook_x, step) in [(true, BOARD_SIZE - 1, 1), (false, 0, -1)] {
+            if !board.has_castling_right(king.color, kingside) {
+                continue;
+            }
+
+            let (low, high) = if rook_x > position.x { (position.x + 1, rook_x) } else { (rook_x + 1, position.x) };
+            let path_clear = (low..high).all(|x| board.piece_at(Position::new(x, home_row)).is_none());
+            let path_safe = [1, 2].iter().all(|&distance| {
+                position.offset(step * distance, 0).is_some_and(|square| attacked & square.bit() == 0)
+            });
+            if path_clear && path_safe {
+                if let Some(target) = position.offset(2 * step, 0) {
+                    positions.push(target);
                 }
-                step += 1;
             }
         }
     }
-}
\ No newline at end of file
+}
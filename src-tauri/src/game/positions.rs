@@ -0,0 +1,70 @@
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use super::board::ChessBoard;
+use super::piece::Color;
+
+/// A starting position the user saved under a name, such as an endgame to practise.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct NamedPosition {
+    pub name: String,
+    pub description: String,
+    pub fen: String,
+}
+
+impl NamedPosition {
+    pub fn board(&self) -> Result<(ChessBoard, Color), String> {
+        ChessBoard::from_fen(&self.fen)
+    }
+}
+
+/// The saved positions in `path`, sorted by name. A missing file means none have been saved yet.
+pub fn list_positions(path: &Path) -> Result<Vec<NamedPosition>, String> {
+    let Ok(json) = fs::read_to_string(path) else {
+        return Ok(Vec::new());
+    };
+    serde_json::from_str(&json).map_err(|error| format!("{} is not a list of positions: {}", path.display(), error))
+}
+
+pub fn find_position(path: &Path, name: &str) -> Result<NamedPosition, String> {
+    list_positions(path)?
+        .into_iter()
+        .find(|position| position.name == name.trim())
+        .ok_or_else(|| format!("There is no saved position named \"{}\"", name.trim()))
+}
+
+/// Saves `position`, replacing any earlier one with the same name. The FEN must describe a legal
+/// position, so that a game can always be started from it.
+pub fn save_position(path: &Path, position: NamedPosition) -> Result<(), String> {
+    let name = position.name.trim().to_string();
+    if name.is_empty() {
+        return Err("A saved position needs a name".to_string());
+    }
+    position.board()?;
+
+    let mut positions = list_positions(path)?;
+    positions.retain(|existing| existing.name != name);
+    positions.push(NamedPosition { name, ..position });
+    positions.sort_by(|a, b| a.name.cmp(&b.name));
+    write_positions(path, &positions)
+}
+
+pub fn delete_position(path: &Path, name: &str) -> Result<(), String> {
+    let mut positions = list_positions(path)?;
+    let before = positions.len();
+    positions.retain(|existing| existing.name != name.trim());
+    if positions.len() == before {
+        return Err(format!("There is no saved position named \"{}\"", name.trim()));
+    }
+    write_positions(path, &positions)
+}
+
+fn write_positions(path: &Path, positions: &[NamedPosition]) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|error| format!("Failed to create {}: {}", dir.display(), error))?;
+    }
+    let json = serde_json::to_string_pretty(positions).map_err(|error| error.to_string())?;
+    fs::write(path, json).map_err(|error| format!("Failed to write {}: {}", path.display(), error))
+}
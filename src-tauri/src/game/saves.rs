@@ -0,0 +1,76 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use serde::Serialize;
+
+use super::state::{GameResult, GameState, SavedGame};
+
+/// A saved game as listed in the load dialog.
+#[derive(Serialize, Clone, Debug)]
+pub struct SavedGameInfo {
+    pub name: String,
+    pub white: String,
+    pub black: String,
+    pub move_count: usize,
+    pub result: Option<GameResult>,
+    // Milliseconds since the Unix epoch
+    pub saved_at: Option<i64>,
+}
+
+/// Writes `game` to `<dir>/<name>.json`, replacing any earlier save under that name.
+pub fn save_game(dir: &Path, name: &str, game: &GameState) -> Result<(), String> {
+    let path = save_path(dir, name)?;
+    fs::create_dir_all(dir).map_err(|error| format!("Failed to create {}: {}", dir.display(), error))?;
+    let json = serde_json::to_string_pretty(&game.to_saved()).map_err(|error| error.to_string())?;
+    fs::write(&path, json).map_err(|error| format!("Failed to write {}: {}", path.display(), error))
+}
+
+pub fn load_game(dir: &Path, name: &str) -> Result<GameState, String> {
+    GameState::from_saved(read_save(&save_path(dir, name)?)?)
+}
+
+/// Lists the games saved in `dir`, skipping files that are not readable saves.
+pub fn list_saved_games(dir: &Path) -> Result<Vec<SavedGameInfo>, String> {
+    // Nothing has been saved yet if the directory does not exist.
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Ok(Vec::new());
+    };
+
+    let mut games: Vec<SavedGameInfo> = entries.flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "json"))
+        .filter_map(|path| {
+            let name = path.file_stem()?.to_string_lossy().into_owned();
+            let saved = read_save(&path).ok()?;
+            let saved_at = fs::metadata(&path).and_then(|metadata| metadata.modified()).ok()
+                .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+                .map(|since_epoch| since_epoch.as_millis() as i64);
+            Some(SavedGameInfo {
+                name,
+                white: saved.players.white,
+                black: saved.players.black,
+                move_count: saved.moves.len(),
+                result: saved.result,
+                saved_at,
+            })
+        })
+        .collect();
+    games.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(games)
+}
+
+fn read_save(path: &Path) -> Result<SavedGame, String> {
+    let json = fs::read_to_string(path).map_err(|error| format!("Failed to read {}: {}", path.display(), error))?;
+    serde_json::from_str(&json).map_err(|error| format!("{} is not a saved game: {}", path.display(), error))
+}
+
+// Names become file names, so they are limited to characters that are safe on every platform
+fn save_path(dir: &Path, name: &str) -> Result<PathBuf, String> {
+    let name = name.trim();
+    let valid = !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || matches!(c, ' ' | '-' | '_'));
+    if !valid {
+        return Err("A save name may only use letters, digits, spaces, '-' and '_'".to_string());
+    }
+    Ok(dir.join(format!("{}.json", name)))
+}
@@ -0,0 +1,218 @@
+use serde::Serialize;
+
+use super::moves::Move;
+use super::piece::{PieceType, Position};
+use super::state::GameState;
+use super::utils::generate_move_notation;
+
+const RESULTS: [&str; 4] = ["1-0", "0-1", "1/2-1/2", "*"];
+
+/// Something the importer had to guess about, or a token it could not place at all.
+#[derive(Serialize, Clone, Debug)]
+pub struct ScoresheetIssue {
+    // 1-based half-move the token was read as
+    pub ply: usize,
+    pub token: String,
+    pub problem: String,
+    // SAN of every legal move the token could have meant
+    pub candidates: Vec<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ScoresheetImport {
+    pub moves: Vec<String>,
+    pub issues: Vec<ScoresheetIssue>,
+    // Tokens after the first one that could not be matched; the game stops there
+    pub unparsed: Vec<String>,
+}
+
+enum TokenMatch {
+    Found(usize),
+    Ambiguous(Vec<usize>),
+    Reinterpreted(usize, String),
+    NotFound(Vec<usize>),
+}
+
+/// Rebuilds a game from a hand-typed move list such as "1.e4 e5 2 Nf3? Nc6 3.Bb5 a6 4.0-0".
+/// Move numbers, annotations, comments and results are ignored, and moves are matched loosely
+/// against the legal moves of the position. Every guess is listed in the report.
+pub fn import_scoresheet(text: &str) -> (GameState, ScoresheetImport) {
+    import_scoresheet_from(GameState::new(), text)
+}
+
+/// Like `import_scoresheet`, but plays the moves on `game`, e.g. a game from a set-up position.
+pub fn import_scoresheet_from(mut game: GameState, text: &str) -> (GameState, ScoresheetImport) {
+    let mut issues = Vec::new();
+    let tokens = tokenize(text);
+
+    for (index, token) in tokens.iter().enumerate() {
+        if game.is_game_over() {
+            return finish_import(game, issues, &tokens[index..]);
+        }
+
+        let ply = index + 1;
+        let legal_moves = game.board().all_legal_moves(game.current_player());
+        let notations: Vec<String> = legal_moves.iter()
+            .map(|mv| generate_move_notation(game.board(), mv.from, mv.to, mv.promotion, false, false))
+            .collect();
+        let describe = |indices: &[usize]| indices.iter().map(|&i| notations[i].clone()).collect::<Vec<_>>();
+
+        let chosen = match match_token(token, &legal_moves, &notations) {
+            TokenMatch::Found(i) => i,
+            TokenMatch::Ambiguous(indices) => {
+                issues.push(ScoresheetIssue {
+                    ply,
+                    token: token.clone(),
+                    problem: format!("Ambiguous move, read as {}", notations[indices[0]]),
+                    candidates: describe(&indices),
+                });
+                indices[0]
+            },
+            TokenMatch::Reinterpreted(i, problem) => {
+                issues.push(ScoresheetIssue { ply, token: token.clone(), problem, candidates: describe(&[i]) });
+                i
+            },
+            TokenMatch::NotFound(indices) => {
+                issues.push(ScoresheetIssue {
+                    ply,
+                    token: token.clone(),
+                    problem: "No legal move matches this token".to_string(),
+                    candidates: describe(&indices),
+                });
+                return finish_import(game, issues, &tokens[index..]);
+            },
+        };
+
+        let chosen = legal_moves[chosen];
+        if game.move_piece_from(chosen.from, chosen.to, chosen.promotion).is_err() {
+            return finish_import(game, issues, &tokens[index..]);
+        }
+    }
+
+    finish_import(game, issues, &[])
+}
+
+fn finish_import(game: GameState, issues: Vec<ScoresheetIssue>, unparsed: &[String]) -> (GameState, ScoresheetImport) {
+    let moves = game.move_history().iter().map(|record| record.san.clone()).collect();
+    let report = ScoresheetImport { moves, issues, unparsed: unparsed.to_vec() };
+    (game, report)
+}
+
+/// Splits the text into move tokens, skipping {comments}, (variations), ;line comments, move
+/// numbers, NAGs and results.
+pub fn tokenize(text: &str) -> Vec<String> {
+    let mut cleaned = String::new();
+    let mut depth = 0usize;
+    let mut in_line_comment = false;
+    for c in text.chars() {
+        match c {
+            '\n' => {
+                in_line_comment = false;
+                cleaned.push(' ');
+            },
+            '{' | '(' => depth += 1,
+            '}' | ')' => depth = depth.saturating_sub(1),
+            _ if depth > 0 || in_line_comment => {},
+            ';' => in_line_comment = true,
+            _ => cleaned.push(c),
+        }
+    }
+
+    cleaned.replace("e.p.", " ")
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter_map(|raw| {
+            // Move numbers may be glued to the move, as in "12.Nf3" or "12...Nf6"
+            let token = raw.rsplit('.').next().unwrap_or(raw);
+            let token = token.trim_end_matches(['!', '?', '+', '#']);
+            let is_noise = token.is_empty()
+                || token.starts_with('$')
+                || RESULTS.contains(&token)
+                || token.chars().all(|c| c.is_ascii_digit());
+            (!is_noise).then(|| token.to_string())
+        })
+        .collect()
+}
+
+// Reduces a move to the characters that identify it: no capture, check or promotion marks, zeros
+// read as the letter O, and lowercase piece letters other than b (a file) capitalised
+fn simplify(notation: &str) -> String {
+    let mut simplified: String = notation.chars()
+        .filter(|c| !matches!(c, 'x' | 'X' | ':' | '+' | '#' | '=' | '-' | '!' | '?'))
+        .map(|c| match c {
+            '0' | 'o' => 'O',
+            _ => c,
+        })
+        .collect();
+    if let Some(first) = simplified.chars().next().filter(|c| matches!(c, 'n' | 'r' | 'q' | 'k')) {
+        simplified.replace_range(..1, &first.to_ascii_uppercase().to_string());
+    }
+    simplified
+}
+
+fn match_token(token: &str, legal_moves: &[Move], notations: &[String]) -> TokenMatch {
+    let key = simplify(token);
+
+    // Proper SAN, give or take the marks simplify() drops.
+    if let Some(i) = notations.iter().position(|notation| simplify(notation) == key) {
+        return TokenMatch::Found(i);
+    }
+
+    // Coordinate notation such as "e2e4", "Ng1f3" or "e7e8q".
+    let coordinates = key.trim_start_matches(['N', 'B', 'R', 'Q', 'K']).to_lowercase();
+    let coordinate_match = legal_moves.iter().position(|mv| {
+        let promotion = mv.promotion.map(|piece_type| piece_type.symbol().to_ascii_lowercase().to_string()).unwrap_or_default();
+        format!("{}{}{}", mv.from.to_algebraic(), mv.to.to_algebraic(), promotion) == coordinates
+    });
+    if let Some(i) = coordinate_match {
+        return TokenMatch::Found(i);
+    }
+
+    match_loosely(&key, legal_moves, notations)
+}
+
+// Falls back to the destination square, narrowed down by the piece letter, any file or rank
+// hints and the promotion piece found in the token
+fn match_loosely(key: &str, legal_moves: &[Move], notations: &[String]) -> TokenMatch {
+    let chars: Vec<char> = key.chars().collect();
+    let Some(square_at) = (0..chars.len().saturating_sub(1)).rev().find(|&i| {
+        ('a'..='h').contains(&chars[i]) && ('1'..='8').contains(&chars[i + 1])
+    }) else {
+        return TokenMatch::NotFound(Vec::new());
+    };
+    let Some(destination) = Position::from_algebraic(&chars[square_at..square_at + 2].iter().collect::<String>()) else {
+        return TokenMatch::NotFound(Vec::new());
+    };
+
+    let named_piece = chars.first().and_then(|&c| PieceType::from_symbol(c)).filter(|_| square_at > 0);
+    let piece_type = named_piece.unwrap_or(PieceType::Pawn);
+    let hints = &chars[usize::from(named_piece.is_some())..square_at];
+    let promotion = chars.get(square_at + 2).and_then(|c| PieceType::from_symbol(c.to_ascii_uppercase()));
+
+    let to_destination: Vec<usize> = (0..legal_moves.len()).filter(|&i| legal_moves[i].to == destination).collect();
+    let candidates: Vec<usize> = to_destination.iter().copied()
+        .filter(|&i| {
+            let from = legal_moves[i].from;
+            notation_piece(&notations[i]) == piece_type
+                && promotion.is_none_or(|wanted| legal_moves[i].promotion == Some(wanted))
+                && hints.iter().all(|&hint| hint == from.file() || hint.to_digit(10) == Some(from.rank() as u32))
+        })
+        .collect();
+
+    match candidates.len() {
+        1 => TokenMatch::Found(candidates[0]),
+        0 if to_destination.len() == 1 => {
+            let i = to_destination[0];
+            TokenMatch::Reinterpreted(i, format!("No matching piece reaches {}, read as {}", destination.to_algebraic(), notations[i]))
+        },
+        0 => TokenMatch::NotFound(to_destination),
+        _ => TokenMatch::Ambiguous(candidates),
+    }
+}
+
+// The moving piece as written at the start of a SAN move
+fn notation_piece(notation: &str) -> PieceType {
+    if notation.starts_with('O') {
+        return PieceType::King;
+    }
+    notation.chars().next().and_then(PieceType::from_symbol).unwrap_or(PieceType::Pawn)
+}
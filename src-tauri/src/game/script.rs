@@ -0,0 +1,137 @@
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::board::ChessBoard;
+use super::cursor::BoardCursor;
+use super::journal::{apply_command, Applied};
+use super::piece::Color;
+use super::state::GameState;
+
+/// One step of a script: any game command with the arguments the frontend would pass it, or one of
+/// the script-only steps `new_game` and `assert`.
+#[derive(Deserialize, Clone, Debug)]
+pub struct ScriptStep {
+    pub command: String,
+    #[serde(default)]
+    pub args: Value,
+}
+
+// Arguments of `new_game`: the usual start, or the position a FEN describes
+#[derive(Deserialize, Default)]
+struct NewGame {
+    fen: Option<String>,
+}
+
+// Arguments of `assert`; every field left out is not checked
+#[derive(Deserialize, Default)]
+#[serde(deny_unknown_fields)]
+struct Expectation {
+    // Only the position is compared, not the move counters
+    fen: Option<String>,
+    current_player: Option<Color>,
+    move_count: Option<usize>,
+    // SAN of the last move played
+    last_move: Option<String>,
+    // "1-0", "0-1", "1/2-1/2", or "*" while the game goes on
+    result: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct StepResult {
+    // 0-based index of the step in the script
+    pub step: usize,
+    pub command: String,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ScriptReport {
+    // True when every step ran and every assertion held
+    pub passed: bool,
+    // The steps that ran; the script stops at the first one that fails
+    pub steps: Vec<StepResult>,
+}
+
+/// Parses a script, a JSON array of steps such as
+/// `[{"command": "new_game"}, {"command": "move_piece", "args": {"from": {"x": 4, "y": 6}, "to": {"x": 4, "y": 4}}}, {"command": "assert", "args": {"move_count": 1}}]`.
+pub fn parse_script(json: &str) -> Result<Vec<ScriptStep>, String> {
+    serde_json::from_str(json).map_err(|error| format!("The script is not a list of steps: {}", error))
+}
+
+/// Runs the steps on `game` in order, stopping at the first failure. `on_step` is told about every
+/// step as soon as it has run.
+pub fn run_script(game: &mut GameState, steps: &[ScriptStep], mut on_step: impl FnMut(&StepResult)) -> ScriptReport {
+    let mut cursor = BoardCursor::new();
+    let mut results = Vec::with_capacity(steps.len());
+    for (index, step) in steps.iter().enumerate() {
+        let result = StepResult { step: index, command: step.command.clone(), error: run_step(game, &mut cursor, step).err() };
+        on_step(&result);
+        let failed = result.error.is_some();
+        results.push(result);
+        if failed {
+            return ScriptReport { passed: false, steps: results };
+        }
+    }
+    ScriptReport { passed: true, steps: results }
+}
+
+fn run_step(game: &mut GameState, cursor: &mut BoardCursor, step: &ScriptStep) -> Result<(), String> {
+    match step.command.as_str() {
+        "new_game" => {
+            let NewGame { fen } = arguments(&step.args)?;
+            *game = match fen {
+                Some(fen) => {
+                    let (board, to_move) = ChessBoard::from_fen(&fen)?;
+                    GameState::from_position(board, to_move)
+                },
+                None => GameState::new(),
+            };
+            *cursor = BoardCursor::new();
+            Ok(())
+        },
+        "assert" => check(game, &arguments(&step.args)?),
+        command => match apply_command(game, cursor, command, &step.args)? {
+            Applied::Replayed => Ok(()),
+            Applied::Skipped => Err(format!("{} does not change the game and cannot be scripted", command)),
+            Applied::External => Err(format!("{} depends on files outside the script", command)),
+        },
+    }
+}
+
+fn check(game: &GameState, expected: &Expectation) -> Result<(), String> {
+    if let Some(fen) = &expected.fen {
+        let (board, to_move) = ChessBoard::from_fen(fen)?;
+        let actual = game.board().position_key(game.current_player());
+        if board.position_key(to_move) != actual {
+            return Err(format!("Expected the position {} but it is {}", board.position_key(to_move), actual));
+        }
+    }
+    if let Some(color) = expected.current_player.filter(|&color| color != game.current_player()) {
+        return Err(format!("Expected {:?} to move but it is {:?}", color, game.current_player()));
+    }
+    if let Some(count) = expected.move_count.filter(|&count| count != game.move_history().len()) {
+        return Err(format!("Expected {} moves but {} were played", count, game.move_history().len()));
+    }
+    if let Some(san) = &expected.last_move {
+        let actual = game.move_history().last().map(|record| record.san.as_str());
+        if actual != Some(san.as_str()) {
+            return Err(format!("Expected the last move to be {} but it is {}", san, actual.unwrap_or("none")));
+        }
+    }
+    if let Some(score) = &expected.result {
+        let actual = game.result().map_or("*", |result| result.score());
+        if score != actual {
+            return Err(format!("Expected the result {} but it is {}", score, actual));
+        }
+    }
+    Ok(())
+}
+
+// Missing arguments read as an empty object, so steps without any can leave `args` out
+fn arguments<T: DeserializeOwned + Default>(args: &Value) -> Result<T, String> {
+    if args.is_null() {
+        return Ok(T::default());
+    }
+    serde_json::from_value(args.clone()).map_err(|error| format!("Bad arguments: {}", error))
+}
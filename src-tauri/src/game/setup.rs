@@ -0,0 +1,80 @@
+use super::board::ChessBoard;
+use super::piece::{Color, Piece, PieceType, Position};
+
+/// Reads a position written out the way books list it, such as
+/// "white: Kg1, Qd1, pawns a2 b2 c2; black: Kg8, Rf8; black to move".
+/// Returns the validated board and the side to move, which is white unless the text says otherwise.
+pub fn parse_setup(text: &str) -> Result<(ChessBoard, Color), String> {
+    let mut pieces: Vec<(Position, Piece)> = Vec::new();
+    let mut to_move = Color::White;
+
+    for section in text.split([';', '\n']).map(str::trim).filter(|section| !section.is_empty()) {
+        let lowercase = section.to_lowercase();
+        if let Some(side) = lowercase.strip_suffix("to move") {
+            to_move = parse_color(side.trim()).ok_or_else(|| format!("Unknown side to move in \"{}\"", section))?;
+            continue;
+        }
+
+        let (side, list) = section.split_once(':')
+            .ok_or_else(|| format!("Expected \"white:\" or \"black:\" before \"{}\"", section))?;
+        let color = parse_color(&side.trim().to_lowercase()).ok_or_else(|| format!("Unknown side \"{}\"", side.trim()))?;
+
+        // Within one comma-separated item a word such as "pawns" applies to every square after it.
+        for item in list.split(',') {
+            let mut named = None;
+            for word in item.split_whitespace().map(|word| word.trim_matches(|c: char| !c.is_alphanumeric())) {
+                if word.is_empty() {
+                    continue;
+                }
+                if let Some(piece_type) = piece_word(&word.to_lowercase()) {
+                    named = Some(piece_type);
+                    continue;
+                }
+
+                let (piece_type, square) = split_piece(word, named)?;
+                let position = Position::from_algebraic(&square.to_lowercase())
+                    .ok_or_else(|| format!("\"{}\" is not a square", word))?;
+                if pieces.iter().any(|&(taken, _)| taken == position) {
+                    return Err(format!("{} is given more than one piece", position.to_algebraic()));
+                }
+                pieces.push((position, Piece::new(piece_type, color)));
+            }
+        }
+    }
+
+    let board = ChessBoard::from_pieces(pieces);
+    board.validate(to_move)?;
+    Ok((board, to_move))
+}
+
+fn parse_color(word: &str) -> Option<Color> {
+    match word {
+        "white" | "w" => Some(Color::White),
+        "black" | "b" => Some(Color::Black),
+        _ => None,
+    }
+}
+
+fn piece_word(word: &str) -> Option<PieceType> {
+    match word.strip_suffix('s').unwrap_or(word) {
+        "king" => Some(PieceType::King),
+        "queen" => Some(PieceType::Queen),
+        "rook" => Some(PieceType::Rook),
+        "bishop" => Some(PieceType::Bishop),
+        "knight" => Some(PieceType::Knight),
+        "pawn" => Some(PieceType::Pawn),
+        _ => None,
+    }
+}
+
+// Splits "Kg1" into a king and g1, while a bare square takes the last piece word or is a pawn
+fn split_piece(word: &str, named: Option<PieceType>) -> Result<(PieceType, &str), String> {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(letter) if letter.is_ascii_uppercase() => {
+            let piece_type = PieceType::from_symbol(letter).ok_or_else(|| format!("Unknown piece letter in \"{}\"", word))?;
+            Ok((piece_type, chars.as_str()))
+        },
+        _ => Ok((named.unwrap_or(PieceType::Pawn), word)),
+    }
+}
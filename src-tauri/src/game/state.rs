@@ -1,30 +1,765 @@
+use std::cmp::Ordering;
+
 use serde::{Deserialize, Serialize};
 
-use super::piece::{Color, Square};
-use super::utils::initial_piece_setup;
+use super::board::{CastlingRights, ChessBoard, BOARD_SIZE};
+use super::eco::{self, Opening};
+use super::error::MoveError;
+use super::moves::{Move, MoveRecord};
+use super::notation::{self, NotationStyle};
+use super::piece::{Color, Piece, PieceType, Position};
+use super::utils::generate_move_notation;
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct PlayerNames {
+    pub white: String,
+    pub black: String,
+}
+
+/// PGN-style headers saying where and when a game was played; empty fields are left out of exports.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct GameMetadata {
+    pub event: String,
+    pub site: String,
+    // In PGN date format, e.g. "2024.05.18"; filled in with the local date when the first move is played
+    pub date: String,
+    pub round: String,
+}
+
+/// How many moves may be taken back in a game.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(tag = "policy")]
+pub enum UndoPolicy {
+    Unlimited,
+    Limited { takebacks: u32 },
+    Disabled,
+}
+
+impl UndoPolicy {
+    // Takebacks a new game starts with, None meaning no limit
+    fn allowance(&self) -> Option<u32> {
+        match *self {
+            UndoPolicy::Unlimited => None,
+            UndoPolicy::Limited { takebacks } => Some(takebacks),
+            UndoPolicy::Disabled => Some(0),
+        }
+    }
+}
+
+/// What the board is being used for. Moves are only played in `Play`; the editor and review each
+/// keep the game untouched until they are left.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(tag = "mode")]
+pub enum GameMode {
+    Play,
+    Edit,
+    // Browsing the position after the first `ply` moves of the game
+    Review { ply: usize },
+}
+
+/// Sent after every move so a pass-and-play board can show whose turn it is and flip itself.
+#[derive(Serialize, Clone, Debug)]
+pub struct TurnChanged {
+    pub current_player: Color,
+    pub player_name: String,
+    // The side that should sit at the bottom of the board
+    pub orientation: Color,
+}
+
+/// How many legal moves each side has in the current position.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Mobility {
+    pub white: usize,
+    pub black: usize,
+}
+
+impl Mobility {
+    pub fn of(board: &ChessBoard) -> Mobility {
+        Mobility {
+            white: board.all_legal_moves(Color::White).len(),
+            black: board.all_legal_moves(Color::Black).len(),
+        }
+    }
+}
+
+/// Pieces taken so far, grouped by the side that took them.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct CapturedPieces {
+    pub by_white: Vec<PieceType>,
+    pub by_black: Vec<PieceType>,
+}
+
+/// Material left on the board for each side, for the advantage bar.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MaterialBalance {
+    pub white: u32,
+    pub black: u32,
+    // White's material minus black's, so positive means white is ahead
+    pub difference: i32,
+}
+
+impl MaterialBalance {
+    pub fn of(board: &ChessBoard) -> MaterialBalance {
+        let white = board.material(Color::White);
+        let black = board.material(Color::Black);
+        MaterialBalance { white, black, difference: white as i32 - black as i32 }
+    }
+}
+
+/// How a finished game ended, serialized with a `reason` tag for the UI.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(tag = "reason")]
+pub enum GameResult {
+    Checkmate { winner: Color },
+    Resignation { winner: Color },
+    Timeout { winner: Color },
+    Stalemate,
+    Repetition,
+    FiftyMove,
+    InsufficientMaterial,
+    Agreement,
+    // Only the score is known, as with the Result header of an imported game
+    Adjudicated { winner: Option<Color> },
+}
+
+impl GameResult {
+    pub fn winner(&self) -> Option<Color> {
+        match *self {
+            GameResult::Checkmate { winner } | GameResult::Resignation { winner } | GameResult::Timeout { winner } => Some(winner),
+            GameResult::Adjudicated { winner } => winner,
+            _ => None,
+        }
+    }
+
+    // Result as written at the end of a score sheet
+    pub fn score(&self) -> &'static str {
+        match self.winner() {
+            Some(Color::White) => "1-0",
+            Some(Color::Black) => "0-1",
+            None => "1/2-1/2",
+        }
+    }
+}
+
+/// What is written to disk for a game: enough to replay it, since everything else follows from the moves.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SavedGame {
+    pub players: PlayerNames,
+    pub metadata: GameMetadata,
+    // The set-up position and side to move, None for the usual start
+    pub start: Option<(ChessBoard, Color)>,
+    pub moves: Vec<MoveRecord>,
+    pub result: Option<GameResult>,
+    #[serde(default)]
+    pub comment: Option<String>,
+}
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct GameState {
-    board: Vec<Vec<Option<Square>>>,
+    #[serde(flatten)]
+    board: ChessBoard,
     current_player: Color,
-    game_over: bool,
+    castling_rights: CastlingRights,
+    mobility: Mobility,
+    captured: CapturedPieces,
+    material: MaterialBalance,
+    result: Option<GameResult>,
+    // Side that has offered a draw the opponent has not answered yet
+    draw_offer: Option<Color>,
+    move_history: Vec<MoveRecord>,
+    players: PlayerNames,
+    metadata: GameMetadata,
+    undo_policy: UndoPolicy,
+    // Takebacks still allowed by the undo policy, None when there is no limit
+    takebacks_left: Option<u32>,
+    // Whether each player only sees their own pieces, with the game acting as the Kriegspiel referee
+    kriegspiel: bool,
+    // Whether the referee refused the side to move's last attempt, announced until a move is played
+    #[serde(skip)]
+    illegal_attempt: bool,
+    // Half-moves since the last capture or pawn move, for the fifty-move rule
+    halfmove_clock: u32,
+    // Starts at 1 and goes up after every black move, as in FEN
+    fullmove_number: u32,
+    // Key of every position reached so far, the current one last, for threefold repetition
+    #[serde(skip)]
+    positions: Vec<String>,
+    // The set-up position the game began from, None for the usual starting position
+    #[serde(skip)]
+    start: Option<(ChessBoard, Color)>,
+    // Comment on the game as a whole, written before the first move in PGN
+    comment: Option<String>,
+    // Latest named opening the game has passed through, for games from the usual start
+    opening: Option<Opening>,
+    mode: GameMode,
 }
 
 impl GameState {
     pub fn new() -> GameState {
-        let mut board = vec![vec![None; 8]; 8]; // Create an 8x8 grid of None
-        for x in 0..8 {
-            for y in 0..8 {
-                board[x][y] = Some(Square::new(x, y, initial_piece_setup(x, y)));
+        GameState::with_board(ChessBoard::new(), Color::White)
+    }
+
+    /// Starts a game from a set-up position with `to_move` to play. A position where that side has
+    /// no legal move is finished straight away.
+    pub fn from_position(board: ChessBoard, to_move: Color) -> GameState {
+        let mut game = GameState::with_board(board.clone(), to_move);
+        game.start = Some((board, to_move));
+        if !game.board.has_legal_moves(to_move) {
+            if game.board.is_in_check(to_move) {
+                game.finish(GameResult::Checkmate { winner: to_move.opposite() });
+            } else {
+                game.finish(GameResult::Stalemate);
             }
         }
+        game
+    }
 
+    fn with_board(board: ChessBoard, to_move: Color) -> GameState {
         GameState {
+            castling_rights: board.castling_rights(),
+            mobility: Mobility::of(&board),
+            captured: CapturedPieces::default(),
+            material: MaterialBalance::of(&board),
+            positions: vec![board.position_key(to_move)],
+            start: None,
             board,
-            current_player: Color::White,
-            game_over: false,
+            current_player: to_move,
+            result: None,
+            draw_offer: None,
+            move_history: Vec::new(),
+            players: PlayerNames::default(),
+            metadata: GameMetadata::default(),
+            undo_policy: UndoPolicy::Unlimited,
+            takebacks_left: None,
+            kriegspiel: false,
+            illegal_attempt: false,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            comment: None,
+            opening: None,
+            mode: GameMode::Play,
         }
     }
-}
 
+    pub fn board(&self) -> &ChessBoard {
+        &self.board
+    }
+
+    pub fn move_history(&self) -> &[MoveRecord] {
+        &self.move_history
+    }
+
+    /// The SAN of every move played, closed by the score once the game has ended, as on a score sheet.
+    pub fn san_history(&self) -> Vec<String> {
+        let mut history: Vec<String> = self.move_history.iter().map(|record| record.san.clone()).collect();
+        if let Some(result) = self.result {
+            history.push(result.score().to_string());
+        }
+        history
+    }
+
+    /// Writes the history entry at 1-based `ply` in `style`, or None past the end of the history.
+    pub fn render_move(&self, ply: usize, style: NotationStyle) -> Option<String> {
+        let index = ply.checked_sub(1)?;
+        self.san_history().get(index).map(|san| notation::render(san, index + self.first_ply(), style))
+    }
+
+    pub fn render_history(&self, style: NotationStyle) -> Vec<String> {
+        self.san_history().iter().enumerate().map(|(index, san)| notation::render(san, index + self.first_ply(), style)).collect()
+    }
+
+    // 1 when a set-up position had black moving first, so that move parity still tells the colors apart
+    fn first_ply(&self) -> usize {
+        self.start.as_ref().map_or(0, |&(_, to_move)| usize::from(to_move == Color::Black))
+    }
+
+    pub fn players(&self) -> &PlayerNames {
+        &self.players
+    }
+
+    pub fn result(&self) -> Option<GameResult> {
+        self.result
+    }
+
+    /// The set-up position the game started from and who moved first, or None for the usual start.
+    pub fn start_position(&self) -> Option<(&ChessBoard, Color)> {
+        self.start.as_ref().map(|(board, to_move)| (board, *to_move))
+    }
+
+    pub fn current_player(&self) -> Color {
+        self.current_player
+    }
+
+    pub fn captured(&self) -> &CapturedPieces {
+        &self.captured
+    }
+
+    pub fn material(&self) -> MaterialBalance {
+        self.material
+    }
+
+    pub fn mobility(&self) -> Mobility {
+        self.mobility
+    }
+
+    pub fn to_fen(&self) -> String {
+        self.board.to_fen(self.current_player, self.halfmove_clock, self.fullmove_number)
+    }
+
+    pub fn halfmove_clock(&self) -> u32 {
+        self.halfmove_clock
+    }
+
+    pub fn fullmove_number(&self) -> u32 {
+        self.fullmove_number
+    }
+
+    pub fn opening(&self) -> Option<&Opening> {
+        self.opening.as_ref()
+    }
+
+    pub fn is_game_over(&self) -> bool {
+        self.result.is_some()
+    }
+
+    pub fn mode(&self) -> GameMode {
+        self.mode
+    }
+
+    pub fn is_editing(&self) -> bool {
+        self.mode == GameMode::Edit
+    }
+
+    /// The guard every gameplay action goes through, so nothing can be played once the game has a
+    /// result, while the board is being edited or while the game is being reviewed.
+    pub fn ensure_in_progress(&self) -> Result<(), MoveError> {
+        if self.is_game_over() {
+            return Err(MoveError::GameAlreadyOver);
+        }
+        self.ensure_playing()
+    }
+
+    // Takebacks only check this, since they are allowed once the game is over
+    fn ensure_playing(&self) -> Result<(), MoveError> {
+        match self.mode {
+            GameMode::Play => Ok(()),
+            GameMode::Edit => Err(MoveError::BoardBeingEdited),
+            GameMode::Review { .. } => Err(MoveError::GameBeingReviewed),
+        }
+    }
+
+    /// Shows the game as it stood after its first `ply` moves without changing it, and keeps the
+    /// game in review mode, where nothing can be played, until `leave_review` is called.
+    pub fn review(&mut self, ply: usize) -> Result<GameState, String> {
+        if self.is_editing() {
+            return Err("Finish editing the board before reviewing the game".to_string());
+        }
+        let mut view = self.position_at(ply)?;
+        self.mode = GameMode::Review { ply };
+        view.mode = self.mode;
+        Ok(view)
+    }
+
+    /// Returns from review to the current position, where play can go on.
+    pub fn leave_review(&mut self) {
+        if let GameMode::Review { .. } = self.mode {
+            self.mode = GameMode::Play;
+        }
+    }
+
+    /// The position on the board as the user sees it: the reviewed one in review mode, otherwise
+    /// the current one.
+    pub fn displayed_position(&self) -> Result<GameState, String> {
+        match self.mode {
+            GameMode::Review { ply } => self.position_at(ply),
+            _ => Ok(self.clone()),
+        }
+    }
+
+    fn finish(&mut self, result: GameResult) {
+        self.result = Some(result);
+        self.draw_offer = None;
+    }
+
+    pub fn set_player_names(&mut self, players: PlayerNames) {
+        self.players = players;
+    }
+
+    pub fn metadata(&self) -> &GameMetadata {
+        &self.metadata
+    }
+
+    pub fn set_metadata(&mut self, metadata: GameMetadata) {
+        self.metadata = metadata;
+    }
+
+    pub fn undo_policy(&self) -> UndoPolicy {
+        self.undo_policy
+    }
+
+    pub fn takebacks_left(&self) -> Option<u32> {
+        self.takebacks_left
+    }
+
+    /// Changes how many moves may be taken back, starting the count afresh.
+    pub fn set_undo_policy(&mut self, policy: UndoPolicy) {
+        self.undo_policy = policy;
+        self.takebacks_left = policy.allowance();
+    }
+
+    pub fn is_kriegspiel(&self) -> bool {
+        self.kriegspiel
+    }
+
+    /// Turns Kriegspiel on or off. The game keeps the full position either way; the commands that
+    /// show it check this first.
+    pub fn set_kriegspiel(&mut self, enabled: bool) {
+        self.kriegspiel = enabled;
+        self.illegal_attempt = false;
+    }
+
+    pub fn illegal_attempt(&self) -> bool {
+        self.illegal_attempt
+    }
 
+    /// Plays a move as the Kriegspiel referee would: every refusal is the same `IllegalMove`, so the
+    /// player learns nothing about the hidden pieces beyond the move being impossible.
+    pub fn referee_move(&mut self, from: Position, to: Position, promotion: Option<PieceType>) -> Result<Move, MoveError> {
+        let played = self.move_piece_from(from, to, promotion).map_err(|_| MoveError::IllegalMove);
+        self.illegal_attempt = played.is_err();
+        played
+    }
+
+    pub fn turn_changed(&self) -> TurnChanged {
+        let player_name = match self.current_player {
+            Color::White => self.players.white.clone(),
+            Color::Black => self.players.black.clone(),
+        };
+        TurnChanged { current_player: self.current_player, player_name, orientation: self.current_player }
+    }
+
+    /// Plays a move for the side to move and ends the game if the opponent is left without legal moves.
+    /// Returns the move as it was played, with any default promotion filled in.
+    pub fn move_piece_from(&mut self, from: Position, to: Position, promotion: Option<PieceType>) -> Result<Move, MoveError> {
+        self.ensure_in_progress()?;
+
+        let piece = self.board.piece_at(from).ok_or(MoveError::PieceMissing)?;
+        if piece.color != self.current_player {
+            return Err(MoveError::NotYourTurn);
+        }
+        let before = self.board.clone();
+        let captured = self.board.move_piece(from, to, promotion)?;
+        let mover = self.current_player;
+        let opponent = mover.opposite();
+        let promoted_to = self.board.piece_at(to)
+            .map(|moved| moved.piece_type)
+            .filter(|&piece_type| piece.piece_type.is_pawn() && !piece_type.is_pawn());
+        let played = before.describe_move(from, to, promoted_to);
+
+        // The notation needs the opponent's check status, so work it out before handing over the turn.
+        let gives_check = self.board.is_in_check(opponent);
+        let has_reply = self.board.has_legal_moves(opponent);
+        let checkmate = gives_check && !has_reply;
+        self.move_history.push(MoveRecord {
+            played,
+            san: generate_move_notation(&before, from, to, promoted_to, gives_check, checkmate),
+            piece: piece.piece_type,
+            captured: captured.map(|captured| captured.piece_type),
+            check: gives_check,
+            checkmate,
+            position_hash: self.board.position_hash(opponent),
+            timestamp: chrono::Utc::now().timestamp_millis(),
+            comment: None,
+            nags: Vec::new(),
+        });
+
+        if let Some(captured) = captured {
+            match mover {
+                Color::White => self.captured.by_white.push(captured.piece_type),
+                Color::Black => self.captured.by_black.push(captured.piece_type),
+            }
+        }
+        self.material = MaterialBalance::of(&self.board);
+
+        if piece.piece_type.is_pawn() || played.is_capture() {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock += 1;
+        }
+        if mover == Color::Black {
+            self.fullmove_number += 1;
+        }
+
+        // An offer only stands until the next move is played.
+        self.draw_offer = None;
+        self.current_player = opponent;
+        // King and rook moves as well as rook captures all show up on the board itself.
+        self.castling_rights = self.board.castling_rights();
+        self.positions.push(self.board.position_key(opponent));
+        if self.start.is_none() {
+            self.opening = eco::classify(&self.positions);
+        }
+        if self.move_history.len() == 1 && self.metadata.date.is_empty() {
+            self.metadata.date = chrono::Local::now().format("%Y.%m.%d").to_string();
+        }
+        self.mobility = Mobility::of(&self.board);
+
+        // No legal reply means checkmate if the opponent is in check and stalemate otherwise.
+        if !has_reply {
+            if gives_check {
+                self.finish(GameResult::Checkmate { winner: mover });
+            } else {
+                self.finish(GameResult::Stalemate);
+            }
+        }
+
+        Ok(played)
+    }
+
+    /// Replaces the game with one starting from a set-up position, keeping the players and the undo
+    /// policy.
+    pub fn set_position(&mut self, board: ChessBoard, to_move: Color) {
+        let players = self.players.clone();
+        let undo_policy = self.undo_policy;
+        *self = GameState::from_position(board, to_move);
+        self.players = players;
+        self.set_undo_policy(undo_policy);
+    }
+
+    /// Opens the board editor on the current position. Pieces can then be placed and removed freely;
+    /// the position is only checked once editing is finished.
+    pub fn start_editing(&mut self) {
+        // An edited position has no previous move, so no pawn can be taken en passant.
+        self.board = ChessBoard::from_pieces(self.board.pieces());
+        self.draw_offer = None;
+        self.mode = GameMode::Edit;
+    }
+
+    pub fn clear_board(&mut self) -> Result<(), String> {
+        self.edit(|pieces| pieces.clear())
+    }
+
+    /// Puts `piece` on `position`, replacing whatever stood there.
+    pub fn place_piece(&mut self, position: Position, piece: Piece) -> Result<(), String> {
+        if position.x >= BOARD_SIZE || position.y >= BOARD_SIZE {
+            return Err(format!("({}, {}) is not a square on the board", position.x, position.y));
+        }
+        self.edit(|pieces| {
+            pieces.retain(|&(square, _)| square != position);
+            pieces.push((position, piece));
+        })
+    }
+
+    pub fn remove_piece(&mut self, position: Position) -> Result<(), String> {
+        self.edit(|pieces| pieces.retain(|&(square, _)| square != position))
+    }
+
+    pub fn set_side_to_move(&mut self, color: Color) -> Result<(), String> {
+        self.ensure_editing()?;
+        self.current_player = color;
+        Ok(())
+    }
+
+    /// Leaves the board editor, starting a new game from the edited position if it is legal. An
+    /// illegal position keeps the editor open so it can be fixed.
+    pub fn finish_editing(&mut self) -> Result<(), String> {
+        self.ensure_editing()?;
+        self.board.validate(self.current_player)?;
+        self.set_position(self.board.clone(), self.current_player);
+        Ok(())
+    }
+
+    fn ensure_editing(&self) -> Result<(), String> {
+        if !self.is_editing() {
+            return Err("The board is not being edited".to_string());
+        }
+        Ok(())
+    }
+
+    // Rebuilds the edited board from its pieces after `change` has rearranged them
+    fn edit(&mut self, change: impl FnOnce(&mut Vec<(Position, Piece)>)) -> Result<(), String> {
+        self.ensure_editing()?;
+        let mut pieces: Vec<(Position, Piece)> = self.board.pieces().collect();
+        change(&mut pieces);
+        self.board = ChessBoard::from_pieces(pieces);
+        self.material = MaterialBalance::of(&self.board);
+        Ok(())
+    }
+
+    /// Takes back the last move, leaving the game as it was before it was played. A result reached
+    /// since then is taken back too. This uses up one of the takebacks the undo policy allows.
+    pub fn undo_move(&mut self) -> Result<(), String> {
+        self.ensure_playing().map_err(|error| error.to_string())?;
+        let plies = self.move_history.len().checked_sub(1).ok_or("There is no move to undo")?;
+        self.take_back(plies)
+    }
+
+    /// Rewinds the game to how it stood after its first `plies` moves, dropping every later move.
+    /// Dropping any moves counts as one takeback; going to the current move changes nothing.
+    pub fn goto_move(&mut self, plies: usize) -> Result<(), String> {
+        self.ensure_playing().map_err(|error| error.to_string())?;
+        match plies.cmp(&self.move_history.len()) {
+            Ordering::Less => self.take_back(plies),
+            Ordering::Equal => Ok(()),
+            Ordering::Greater => Err(format!("The game only has {} moves", self.move_history.len())),
+        }
+    }
+
+    fn take_back(&mut self, plies: usize) -> Result<(), String> {
+        let takebacks_left = match (self.takebacks_left, self.undo_policy) {
+            (Some(0), UndoPolicy::Disabled) => return Err("Takebacks are disabled in this game".to_string()),
+            (Some(0), _) => return Err("There are no takebacks left in this game".to_string()),
+            (left, _) => left.map(|left| left - 1),
+        };
+        *self = self.position_at(plies)?;
+        self.takebacks_left = takebacks_left;
+        Ok(())
+    }
+
+    /// Rebuilds the game as it stood after its first `plies` moves, 0 being the starting position, by
+    /// playing them again from the start. Players, headers, comments, the undo policy and the
+    /// original move times are kept.
+    pub fn position_at(&self, plies: usize) -> Result<GameState, String> {
+        if plies > self.move_history.len() {
+            return Err(format!("The game only has {} moves", self.move_history.len()));
+        }
+
+        let mut game = GameState::replay(self.players.clone(), self.start.clone(), &self.move_history[..plies])?;
+        game.metadata = self.metadata.clone();
+        game.comment = self.comment.clone();
+        game.undo_policy = self.undo_policy;
+        game.takebacks_left = self.takebacks_left;
+        game.kriegspiel = self.kriegspiel;
+        Ok(game)
+    }
+
+    // Plays `moves` again from the start position, or the usual one, keeping the original move times
+    // and annotations
+    fn replay(players: PlayerNames, start: Option<(ChessBoard, Color)>, moves: &[MoveRecord]) -> Result<GameState, String> {
+        let mut game = match start {
+            Some((board, to_move)) => GameState::from_position(board, to_move),
+            None => GameState::new(),
+        };
+        game.players = players;
+        for record in moves {
+            let played = record.played;
+            game.move_piece_from(played.from, played.to, played.promotion).map_err(|error| error.to_string())?;
+            if let Some(replayed) = game.move_history.last_mut() {
+                replayed.timestamp = record.timestamp;
+                replayed.comment = record.comment.clone();
+                replayed.nags = record.nags.clone();
+            }
+        }
+        Ok(game)
+    }
+
+    pub fn to_saved(&self) -> SavedGame {
+        SavedGame {
+            players: self.players.clone(),
+            metadata: self.metadata.clone(),
+            start: self.start.clone(),
+            moves: self.move_history.clone(),
+            result: self.result,
+            comment: self.comment.clone(),
+        }
+    }
+
+    /// Rebuilds a saved game by replaying its moves, then restores a result reached off the board.
+    pub fn from_saved(saved: SavedGame) -> Result<GameState, String> {
+        let mut game = GameState::replay(saved.players, saved.start, &saved.moves)?;
+        game.metadata = saved.metadata;
+        game.comment = saved.comment;
+        if let Some(result) = saved.result.filter(|_| !game.is_game_over()) {
+            game.adjudicate(result)?;
+        }
+        Ok(game)
+    }
+
+    pub fn comment(&self) -> Option<&str> {
+        self.comment.as_deref()
+    }
+
+    /// Sets the comment on the game as a whole; an empty comment removes it.
+    pub fn annotate_game(&mut self, comment: Option<String>) {
+        self.comment = comment.filter(|comment| !comment.trim().is_empty());
+    }
+
+    /// Replaces the comment and NAGs of the move at 1-based `ply`. An empty comment removes it.
+    pub fn annotate_move(&mut self, ply: usize, comment: Option<String>, nags: Vec<u8>) -> Result<(), String> {
+        let count = self.move_history.len();
+        let record = ply.checked_sub(1)
+            .and_then(|index| self.move_history.get_mut(index))
+            .ok_or_else(|| format!("There is no move {} in a game of {} moves", ply, count))?;
+        record.comment = comment.filter(|comment| !comment.trim().is_empty());
+        record.nags = nags;
+        Ok(())
+    }
+
+    /// Ends the game with `color` giving up.
+    pub fn resign(&mut self, color: Color) -> Result<(), String> {
+        self.ensure_in_progress().map_err(|error| error.to_string())?;
+
+        self.finish(GameResult::Resignation { winner: color.opposite() });
+        Ok(())
+    }
+
+    /// Records a result decided away from the board, e.g. the one given in an imported game's headers.
+    pub fn adjudicate(&mut self, result: GameResult) -> Result<(), String> {
+        self.ensure_in_progress().map_err(|error| error.to_string())?;
+
+        self.finish(result);
+        Ok(())
+    }
+
+    pub fn offer_draw(&mut self, color: Color) -> Result<(), String> {
+        self.ensure_in_progress().map_err(|error| error.to_string())?;
+        if self.draw_offer.is_some() {
+            return Err("A draw offer is already pending".to_string());
+        }
+
+        self.draw_offer = Some(color);
+        Ok(())
+    }
+
+    /// Ends the game drawn if `color` may claim it by threefold repetition or the fifty-move rule,
+    /// otherwise explains why the claim is refused.
+    pub fn claim_draw(&mut self, color: Color) -> Result<(), String> {
+        self.ensure_in_progress().map_err(|error| error.to_string())?;
+        if color != self.current_player {
+            return Err("A draw can only be claimed on your own turn".to_string());
+        }
+
+        let current = self.positions.last();
+        let repetitions = self.positions.iter().filter(|&position| Some(position) == current).count();
+        if repetitions >= 3 {
+            self.finish(GameResult::Repetition);
+        } else if self.halfmove_clock >= 100 {
+            self.finish(GameResult::FiftyMove);
+        } else {
+            return Err(format!(
+                "The position has occurred {} time(s) and {} move(s) have passed without a capture or pawn move",
+                repetitions,
+                self.halfmove_clock / 2,
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Answers the pending draw offer on behalf of `color`; accepting ends the game drawn.
+    pub fn respond_draw(&mut self, color: Color, accept: bool) -> Result<(), String> {
+        self.ensure_in_progress().map_err(|error| error.to_string())?;
+        match self.draw_offer {
+            Some(offered_by) if offered_by != color => {},
+            Some(_) => return Err("You cannot answer your own draw offer".to_string()),
+            None => return Err("There is no draw offer to answer".to_string()),
+        }
+
+        self.draw_offer = None;
+        if accept {
+            self.finish(GameResult::Agreement);
+        }
+
+        Ok(())
+    }
+}
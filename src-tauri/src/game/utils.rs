@@ -1,21 +1,83 @@
-use super::piece::{Piece, PieceType, Color};
+use super::board::ChessBoard;
+use super::piece::{Piece, PieceType, Color, Position};
 
 /// Returns the initial piece setup for a given position.
 pub fn initial_piece_setup(col: usize, row: usize) -> Option<Piece> {
-    let color = if row < 2 { Color::White } else { Color::Black };
+    // Black starts on the top rows so that white pawns advance towards row 0
+    let color = if row < 2 { Color::Black } else { Color::White };
     match (row, col) {
         // Pawns
-        (1, _) | (6, _) => Some(Piece { piece_type: PieceType::Pawn, color }),
+        (1, _) | (6, _) => Some(Piece::new(PieceType::Pawn, color)),
         // Rooks
-        (0, 0) | (0, 7) | (7, 0) | (7, 7) => Some(Piece { piece_type: PieceType::Rook, color }),
+        (0, 0) | (0, 7) | (7, 0) | (7, 7) => Some(Piece::new(PieceType::Rook, color)),
         // Knights
-        (0, 1) | (0, 6) | (7, 1) | (7, 6) => Some(Piece { piece_type: PieceType::Knight, color }),
+        (0, 1) | (0, 6) | (7, 1) | (7, 6) => Some(Piece::new(PieceType::Knight, color)),
         // Bishops
-        (0, 2) | (0, 5) | (7, 2) | (7, 5) => Some(Piece { piece_type: PieceType::Bishop, color }),
+        (0, 2) | (0, 5) | (7, 2) | (7, 5) => Some(Piece::new(PieceType::Bishop, color)),
         // Queens
-        (0, 3) | (7, 3) => Some(Piece { piece_type: PieceType::Queen, color }),
+        (0, 3) | (7, 3) => Some(Piece::new(PieceType::Queen, color)),
         // Kings
-        (0, 4) | (7, 4) => Some(Piece { piece_type: PieceType::King, color }),
+        (0, 4) | (7, 4) => Some(Piece::new(PieceType::King, color)),
         _ => None,
     }
 }
+
+/// Describes a move in Standard Algebraic Notation, e.g. "Nbd2", "exd6", "e8=Q+" or "O-O".
+/// `board` is the position before the move, while `gives_check` and `is_checkmate` describe the
+/// opponent's position after it.
+pub fn generate_move_notation(board: &ChessBoard, from: Position, to: Position, promotion: Option<PieceType>, gives_check: bool, is_checkmate: bool) -> String {
+    let Some(piece) = board.piece_at(from) else {
+        return String::new();
+    };
+    let is_capture = board.piece_at(to).is_some() || board.is_en_passant(from, to);
+
+    let mut notation = if piece.piece_type == PieceType::King && from.x.abs_diff(to.x) == 2 {
+        let castle = if to.x > from.x { "O-O" } else { "O-O-O" };
+        castle.to_string()
+    } else if piece.piece_type.is_pawn() {
+        let mut pawn_move = if is_capture {
+            format!("{}x{}", from.file(), to.to_algebraic())
+        } else {
+            to.to_algebraic()
+        };
+        if let Some(piece_type) = promotion {
+            pawn_move.push('=');
+            pawn_move.push(piece_type.symbol());
+        }
+        pawn_move
+    } else {
+        let capture = if is_capture { "x" } else { "" };
+        format!("{}{}{}{}", piece.piece_type.symbol(), disambiguation(board, piece, from, to), capture, to.to_algebraic())
+    };
+
+    if is_checkmate {
+        notation.push('#');
+    } else if gives_check {
+        notation.push('+');
+    }
+    notation
+}
+
+// When another piece of the same kind can also reach `to`, names the file of `from`, or its rank if
+// the file is shared, or both if neither is unique.
+fn disambiguation(board: &ChessBoard, piece: Piece, from: Position, to: Position) -> String {
+    let rivals: Vec<Position> = board.pieces()
+        .filter(|&(position, other)| {
+            position != from
+                && other.piece_type == piece.piece_type
+                && other.color == piece.color
+                && board.legal_moves(position).contains(&to)
+        })
+        .map(|(position, _)| position)
+        .collect();
+
+    if rivals.is_empty() {
+        String::new()
+    } else if rivals.iter().all(|rival| rival.x != from.x) {
+        from.file().to_string()
+    } else if rivals.iter().all(|rival| rival.y != from.y) {
+        from.rank().to_string()
+    } else {
+        from.to_algebraic()
+    }
+}
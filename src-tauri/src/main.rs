@@ -1,13 +1,33 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
+mod appearance;
 mod commands;
 mod game;
 
+use std::fs;
+use std::sync::Mutex;
+
+use tauri::Manager;
+
+use game::{analysis::AnalysisBoard, board::ChessBoard, cursor::BoardCursor, database::GameDatabase, journal::Journal, pgn::PgnLibrary, piece::Color, state::GameState};
 
 
 fn main() {
     tauri::Builder::default()
-        .invoke_handler(tauri::generate_handler![commands::get_game_state, commands::get_possible_moves, commands::current_time, commands::greet])
+        .manage(Mutex::new(GameState::new()))
+        .manage(Mutex::new(AnalysisBoard::new(ChessBoard::new(), Color::White)))
+        .manage(Mutex::new(PgnLibrary::default()))
+        .manage(Mutex::new(BoardCursor::new()))
+        .setup(|app| {
+            // The database lives in the app data directory, which is only known once the app exists.
+            let data_dir = app.path_resolver().app_data_dir().ok_or("Could not find the app data directory")?;
+            fs::create_dir_all(&data_dir)?;
+            app.manage(Mutex::new(GameDatabase::open(&data_dir.join("games.sqlite"))?));
+            // Each session starts a new journal, so the file holds the commands of the last run.
+            app.manage(Mutex::new(Journal::create(&data_dir.join("journal.jsonl"))));
+            Ok(())
+        })
+        .invoke_handler(commands::journaled(tauri::generate_handler![commands::get_game_state, commands::move_piece, commands::cursor_move, commands::cursor_select, commands::undo_move, commands::set_undo_policy, commands::goto_move, commands::get_position_at, commands::enter_review, commands::leave_review, commands::set_player_names, commands::set_game_metadata, commands::resign, commands::offer_draw, commands::respond_draw, commands::claim_draw, commands::import_scoresheet, commands::setup_position, commands::start_editing, commands::clear_board, commands::place_piece, commands::remove_piece, commands::set_side_to_move, commands::finish_editing, commands::read_epd_file, commands::load_epd_position, commands::check_epd_move, commands::get_epd, commands::start_analysis, commands::get_analysis, commands::analysis_move, commands::analysis_go_to, commands::get_fen, commands::get_pgn, commands::export_pgn, commands::render_board_image, commands::copy_fen_to_clipboard, commands::copy_pgn_to_clipboard, commands::preview_pgn, commands::import_pgn_games, commands::list_imported_games, commands::open_imported_game, commands::save_game, commands::load_game, commands::list_saved_games, commands::list_named_positions, commands::save_named_position, commands::delete_named_position, commands::start_new_game_from_position, commands::list_piece_sets, commands::list_board_themes, commands::get_appearance, commands::set_piece_set, commands::set_board_theme, commands::set_kriegspiel, commands::get_kriegspiel_view, commands::kriegspiel_move, commands::search_games, commands::load_stored_game, commands::get_stored_pgn, commands::find_games_with_position, commands::rebuild_index, commands::get_monthly_digest, commands::get_opening_info, commands::get_material_balance, commands::annotate_move, commands::annotate_game, commands::render_move, commands::render_history, commands::get_legal_moves, commands::get_possible_moves, commands::replay_journal, commands::run_script, commands::current_time, commands::greet]))
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
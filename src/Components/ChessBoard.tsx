@@ -17,18 +17,7 @@ const Chessboard: React.FC = () => {
 
     // Handling clicks directly based on the actual x, y from the UI
     const handleSquareClick = (x: number, y: number) => {
-        const fullBoard = gameState.board.map(row =>
-            row.map(square => ({
-                x: square.x,
-                y: square.y,
-                piece: square.piece ? {
-                    piece_type: square.piece.piece_type,
-                    color: square.piece.color
-                } : null  // This should correctly serialize to None in Rust
-            }))
-        );
-    
-        invoke<Position[]>('get_possible_moves', { x, y, board: fullBoard })
+        invoke<Position[]>('get_possible_moves', { x, y })
             .then((moves: Position[]) => {
                 const newBoard = gameState.board.map((row, rowIndex) =>
                     row.map((square, colIndex) => ({
@@ -1,17 +1,118 @@
 import React, { createContext, useContext, useEffect, useState } from 'react';
 import { invoke } from '@tauri-apps/api/tauri';
-import { Color, Square } from '../types';
+import { Color, PieceType, Position, Square } from '../types';
+
+interface CastlingRights {
+    white_kingside: boolean;
+    white_queenside: boolean;
+    black_kingside: boolean;
+    black_queenside: boolean;
+}
+
+interface MoveRecord {
+    from: Position;
+    to: Position;
+    promotion: PieceType | null;
+    move_type: 'Normal' | 'Capture' | 'DoublePawnPush' | 'EnPassant' | 'KingsideCastle' | 'QueensideCastle';
+    san: string;
+    piece: PieceType;
+    captured: PieceType | null;
+    check: boolean;
+    checkmate: boolean;
+    position_hash: string; // 16 hex digits
+    timestamp: number;
+    comment: string | null;
+    nags: number[];
+}
+
+interface CapturedPieces {
+    by_white: PieceType[];
+    by_black: PieceType[];
+}
+
+interface MaterialBalance {
+    white: number;
+    black: number;
+    difference: number;
+}
+
+interface Mobility {
+    white: number;
+    black: number;
+}
+
+interface PlayerNames {
+    white: string;
+    black: string;
+}
+
+interface GameMetadata {
+    event: string;
+    site: string;
+    date: string;
+    round: string;
+}
+
+type UndoPolicy =
+    | { policy: 'Unlimited' | 'Disabled' }
+    | { policy: 'Limited'; takebacks: number };
+
+type GameMode =
+    | { mode: 'Play' | 'Edit' }
+    | { mode: 'Review'; ply: number };
+
+interface Opening {
+    eco: string;
+    name: string;
+}
+
+type GameResult =
+    | { reason: 'Checkmate' | 'Resignation' | 'Timeout'; winner: Color }
+    | { reason: 'Stalemate' | 'Repetition' | 'FiftyMove' | 'InsufficientMaterial' | 'Agreement' }
+    | { reason: 'Adjudicated'; winner: Color | null };
 
 interface GameState {
     board: Square[][];
     current_player: Color;
-    game_over: boolean;
+    castling_rights: CastlingRights;
+    mobility: Mobility;
+    captured: CapturedPieces;
+    material: MaterialBalance;
+    en_passant: Position | null;
+    result: GameResult | null;
+    move_history: MoveRecord[];
+    halfmove_clock: number;
+    fullmove_number: number;
+    players: PlayerNames;
+    metadata: GameMetadata;
+    undo_policy: UndoPolicy;
+    takebacks_left: number | null;
+    kriegspiel: boolean;
+    comment: string | null;
+    opening: Opening | null;
+    mode: GameMode;
 }
 
 const initialState: GameState = {
     board: [], // This would be initialized properly with the starting position
     current_player: Color.White,
-    game_over: false,
+    castling_rights: { white_kingside: true, white_queenside: true, black_kingside: true, black_queenside: true },
+    mobility: { white: 20, black: 20 },
+    captured: { by_white: [], by_black: [] },
+    material: { white: 39, black: 39, difference: 0 },
+    en_passant: null,
+    result: null,
+    move_history: [],
+    halfmove_clock: 0,
+    fullmove_number: 1,
+    players: { white: '', black: '' },
+    metadata: { event: '', site: '', date: '', round: '' },
+    undo_policy: { policy: 'Unlimited' },
+    takebacks_left: null,
+    kriegspiel: false,
+    comment: null,
+    opening: null,
+    mode: { mode: 'Play' },
 };
 
 interface ChessContextType {
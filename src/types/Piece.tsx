@@ -3,5 +3,6 @@ import { Color, PieceType } from ".";
 export interface Piece {
     piece_type: PieceType;
     color: Color;
+    has_moved?: boolean;
 }
 